@@ -1,5 +1,6 @@
 mod parser;
 mod part1;
 mod part2;
+pub mod simulation;
 
-pub use parser::Problem;
+pub use parser::{parse_jet_pattern, JetPatternError, Problem};
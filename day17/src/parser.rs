@@ -1,3 +1,4 @@
+use std::fmt;
 use utils::parser::ParseInput;
 
 pub type ParsedOutput = Vec<u8>;
@@ -11,6 +12,96 @@ impl ParseInput for Problem {
     type ParsedType = ParsedOutput;
     fn parse(&mut self, input: String, part2: bool) -> ParsedOutput {
         self.total_number_of_rocks = if part2 { 1000000000000 } else { 2022 };
-        input.as_bytes().to_vec()
+        parse_jet_pattern(&input).unwrap_or_else(|err| panic!("Invalid jet pattern: {}", err))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JetPatternError {
+    InvalidJet { offset: usize, byte: u8 },
+    Empty,
+}
+
+impl fmt::Display for JetPatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JetPatternError::InvalidJet { offset, byte } => write!(
+                f,
+                "unexpected byte '{}' at offset {}, expected '<' or '>'",
+                byte.escape_ascii(),
+                offset
+            ),
+            JetPatternError::Empty => write!(f, "pattern has no jets"),
+        }
+    }
+}
+
+impl std::error::Error for JetPatternError {}
+
+// Keeps only the `<` and `>` jets, whitespace (including line breaks) anywhere in the input is
+// ignored and anything else is rejected with its byte offset in the input
+pub fn parse_jet_pattern(input: &str) -> Result<Vec<u8>, JetPatternError> {
+    let mut jet_order = Vec::with_capacity(input.len());
+
+    for (offset, byte) in input.bytes().enumerate() {
+        match byte {
+            b'<' | b'>' => jet_order.push(byte),
+            _ if byte.is_ascii_whitespace() => {}
+            _ => return Err(JetPatternError::InvalidJet { offset, byte }),
+        }
+    }
+
+    if jet_order.is_empty() {
+        return Err(JetPatternError::Empty);
+    }
+
+    Ok(jet_order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_newline_is_accepted() {
+        assert_eq!(parse_jet_pattern(">><<>\n"), Ok(b">><<>".to_vec()));
+        assert_eq!(parse_jet_pattern(">><<>\r\n"), Ok(b">><<>".to_vec()));
+    }
+
+    #[test]
+    fn whitespace_in_the_middle_is_ignored() {
+        assert_eq!(parse_jet_pattern(" >> <\t<\n>\n"), Ok(b">><<>".to_vec()));
+    }
+
+    #[test]
+    fn invalid_jet_reports_its_byte_offset() {
+        let err = parse_jet_pattern(">> <x<").unwrap_err();
+        assert_eq!(
+            err,
+            JetPatternError::InvalidJet {
+                offset: 4,
+                byte: b'x'
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "unexpected byte 'x' at offset 4, expected '<' or '>'"
+        );
+
+        // Offsets count bytes, not characters
+        assert_eq!(
+            parse_jet_pattern("<é>"),
+            Err(JetPatternError::InvalidJet {
+                offset: 1,
+                byte: 0xc3
+            })
+        );
+    }
+
+    #[test]
+    fn no_jets_is_empty() {
+        assert_eq!(parse_jet_pattern(""), Err(JetPatternError::Empty));
+        assert_eq!(parse_jet_pattern(" \n\t\r\n"), Err(JetPatternError::Empty));
+        assert_eq!(JetPatternError::Empty.to_string(), "pattern has no jets");
     }
 }
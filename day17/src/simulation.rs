@@ -0,0 +1,493 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// How often the brute force run drops rows that can no longer be reached
+pub const PRUNE_INTERVAL: u64 = 1000;
+
+#[derive(Eq, Hash, Debug, PartialEq, Copy, Clone)]
+pub struct Point(pub i128, pub i128);
+
+pub const DASH: [Point; 4] = [Point(0, 0), Point(1, 0), Point(2, 0), Point(3, 0)];
+
+pub const PLUS: [Point; 4] = [Point(1, 0), Point(0, 1), Point(2, 1), Point(1, 2)];
+
+pub const L: [Point; 5] = [
+    Point(0, 0),
+    Point(1, 0),
+    Point(2, 0),
+    Point(2, 1),
+    Point(2, 2),
+];
+
+pub const PIPE: [Point; 4] = [Point(0, 0), Point(0, 1), Point(0, 2), Point(0, 3)];
+
+pub const SQUARE: [Point; 4] = [Point(0, 0), Point(0, 1), Point(1, 0), Point(1, 1)];
+
+#[derive(Eq, Debug, PartialEq, Copy, Clone)]
+// Root point in the bottom left point
+pub enum Rock {
+    Dash { point: Point },
+    Plus { point: Point },
+    L { point: Point },
+    Pipe { point: Point },
+    Square { point: Point },
+}
+
+impl Rock {
+    pub fn new_dash() -> Rock {
+        Rock::Dash { point: Point(0, 0) }
+    }
+    pub fn new_plus() -> Rock {
+        Rock::Plus { point: Point(0, 0) }
+    }
+    pub fn new_l() -> Rock {
+        Rock::L { point: Point(0, 0) }
+    }
+    pub fn new_pipe() -> Rock {
+        Rock::Pipe { point: Point(0, 0) }
+    }
+    pub fn new_square() -> Rock {
+        Rock::Square { point: Point(0, 0) }
+    }
+
+    pub fn get_rock_coordinates(&self, point: Point) -> Vec<Point> {
+        let x_index = point.0;
+        let y_index = point.1;
+
+        let points = self.get_coordinate_offsets();
+
+        let mut new_points: Vec<Point> = vec![Point(0, 0); points.len()];
+
+        points
+            .iter()
+            .enumerate()
+            .for_each(|(i, p)| new_points[i] = Point(p.0 + x_index, p.1 + y_index));
+
+        new_points
+    }
+
+    pub fn get_point(&self) -> Point {
+        match self {
+            Rock::Dash { point }
+            | Rock::Plus { point }
+            | Rock::L { point }
+            | Rock::Pipe { point }
+            | Rock::Square { point } => *point,
+        }
+    }
+
+    pub fn set_point(&mut self, p: Point) {
+        match self {
+            Rock::Dash { point }
+            | Rock::Plus { point }
+            | Rock::L { point }
+            | Rock::Pipe { point }
+            | Rock::Square { point } => {
+                point.0 = p.0;
+                point.1 = p.1;
+            }
+        }
+    }
+
+    fn get_coordinate_offsets(&self) -> &'static [Point] {
+        match self {
+            Rock::Dash { point: _ } => &DASH,
+            Rock::Plus { point: _ } => &PLUS,
+            Rock::L { point: _ } => &L,
+            Rock::Pipe { point: _ } => &PIPE,
+            Rock::Square { point: _ } => &SQUARE,
+        }
+    }
+}
+
+// Record of a single rock once it has come to rest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RockEvent {
+    // 1 based number of the rock in the order it was spawned
+    pub rock_number: u64,
+    pub rock: Rock,
+    pub position: Point,
+    // Indexes into the jet pattern used while this rock was falling, in order
+    pub jet_indices: Vec<usize>,
+    // Height of the tower after this rock settled
+    pub max_height: i128,
+    // Rows this rock completed from wall to wall
+    pub sealed_rows: Vec<i128>,
+}
+
+pub struct Algo {
+    pub jet_order: Vec<u8>,
+    pub rocks_order: [Rock; 5],
+    pub max_y: i128,
+    pub stationary_rocks: HashMap<Point, bool>,
+    pub current_rock_index: i8,
+    pub current_jet_index: usize,
+    pub max_x: u8,
+    pub current_rock: Rock,
+    pub rock_count: u64,
+    // Absolute y of the lowest row still kept in `stationary_rocks`, everything below it is
+    // unreachable and treated as full
+    pub floor_y: i128,
+    // Prune unreachable rows every n rocks, disabled when None
+    pub prune_interval: Option<u64>,
+    // (length, height)
+    pub state_id_map: HashMap<String, (u64, i128)>,
+}
+
+impl Algo {
+    pub fn new(jet_order: Vec<u8>) -> Algo {
+        let mut stationary_rocks = HashMap::new();
+        stationary_rocks.insert(Point(0, -1), true);
+        stationary_rocks.insert(Point(1, -1), true);
+        stationary_rocks.insert(Point(2, -1), true);
+        stationary_rocks.insert(Point(3, -1), true);
+        stationary_rocks.insert(Point(4, -1), true);
+        stationary_rocks.insert(Point(5, -1), true);
+        stationary_rocks.insert(Point(6, -1), true);
+
+        Algo {
+            max_y: -1,
+            stationary_rocks,
+            current_rock: Rock::new_dash(),
+            rock_count: 0,
+            floor_y: -1,
+            prune_interval: None,
+            current_jet_index: 0,
+            current_rock_index: 0,
+            max_x: 6,
+            jet_order,
+            rocks_order: [
+                Rock::new_dash(),
+                Rock::new_plus(),
+                Rock::new_l(),
+                Rock::new_pipe(),
+                Rock::new_square(),
+            ],
+            state_id_map: HashMap::new(),
+        }
+    }
+
+    // Drops `total_number_of_rocks` rocks and returns the height of the tower, skipping ahead
+    // once the falling pattern starts repeating
+    pub fn get_tower_height(&mut self, total_number_of_rocks: u64, verbose: bool) -> i128 {
+        let mut rock_number = 0;
+
+        let mut max_rocks = total_number_of_rocks;
+        let mut skipped_rocks_height = 0;
+
+        if verbose {
+            println!("Total number of rocks: {}", total_number_of_rocks);
+        }
+
+        loop {
+            rock_number += 1;
+            if rock_number > max_rocks {
+                if verbose {
+                    println!("Breaking on rock {}", rock_number);
+                }
+                break;
+            }
+
+            self.spawn_rock_and_run_till_stationary();
+
+            let state_id = self.get_state_id();
+
+            if verbose {
+                println!(
+                    "Rock no: {} out of {}. Max y index is {}. State id: {}",
+                    rock_number, max_rocks, self.max_y, state_id
+                );
+            }
+
+            if let Some((prev_rock_no, prev_max_y)) = self.state_id_map.get(&state_id) {
+                let delta_rock_no = rock_number - prev_rock_no;
+                let delta_max_y = self.max_y - prev_max_y;
+
+                if verbose {
+                    println!(
+                        "Found repeat: length = {}, height = {}",
+                        delta_rock_no, delta_max_y
+                    );
+                }
+
+                let deltas = (max_rocks - rock_number) / delta_rock_no;
+
+                if deltas > 0 {
+                    skipped_rocks_height += deltas as i128 * delta_max_y;
+                    max_rocks -= deltas * delta_rock_no;
+
+                    if verbose {
+                        println!("current max y: {}", self.max_y);
+                        println!("Delta rock no: {}", delta_rock_no);
+                        println!("Delta max y: {}", delta_max_y);
+                        println!("Deltas: {}", deltas);
+                        println!("Skipped rocks height: {}", skipped_rocks_height);
+                        println!("New total rocks: {}", max_rocks);
+                    }
+                    continue;
+                }
+            }
+
+            self.state_id_map
+                .insert(state_id.clone(), (rock_number, self.max_y));
+        }
+
+        self.max_y + skipped_rocks_height + 1
+    }
+
+    pub fn spawn_rock(&mut self) {
+        self.current_rock = self.rocks_order[self.current_rock_index as usize];
+        self.current_rock_index = (self.current_rock_index + 1) % self.rocks_order.len() as i8;
+
+        let x_index = 2;
+        let y_index = 4 + self.max_y;
+
+        self.current_rock.set_point(Point(x_index, y_index));
+    }
+
+    pub fn is_point_full(&self, point: &Point) -> bool {
+        point.0 < 0
+            || point.0 > self.max_x as i128
+            || point.1 < 0
+            || point.1 < self.floor_y
+            || self.stationary_rocks.contains_key(point)
+    }
+
+    pub fn move_horizontal(&mut self) {
+        let point = self.current_rock.get_point();
+
+        let new_point = match self.jet_order[self.current_jet_index] {
+            b'<' => Point(point.0 - 1, point.1),
+            b'>' => Point(point.0 + 1, point.1),
+            _ => panic!("Invalid move"),
+        };
+
+        self.current_jet_index = (self.current_jet_index + 1) % self.jet_order.len();
+
+        let is_any_point_full = self
+            .current_rock
+            .get_rock_coordinates(new_point)
+            .iter()
+            .any(|p| self.is_point_full(p));
+
+        if !is_any_point_full {
+            self.current_rock.set_point(new_point);
+        }
+    }
+
+    // Returns true if rock has is at bottom and can't move anymore
+    pub fn move_vertical(&mut self) -> bool {
+        let point = self.current_rock.get_point();
+        let new_point = Point(point.0, point.1 - 1);
+
+        let rock_new_co_ordinates = self.current_rock.get_rock_coordinates(new_point);
+
+        let is_any_point_full = rock_new_co_ordinates.iter().any(|p| self.is_point_full(p));
+
+        if !is_any_point_full {
+            self.current_rock.set_point(new_point);
+            // println!("Rest pos after vertical movement: {:?}", self.current_rock);
+            false
+        } else {
+            let rock_new_co_ordinates = self.current_rock.get_rock_coordinates(point);
+            rock_new_co_ordinates.iter().for_each(|p| {
+                self.stationary_rocks.insert(*p, true);
+
+                self.max_y = std::cmp::max(self.max_y, p.1);
+            });
+            // println!("Can't move down: {:?}", self.current_rock);
+            true
+        }
+    }
+
+    pub fn spawn_rock_and_run_till_stationary(&mut self) -> RockEvent {
+        self.spawn_rock();
+        self.rock_count += 1;
+
+        // println!("Rest pos : {:?}", self.current_rock);
+
+        let mut jet_indices = vec![];
+
+        loop {
+            jet_indices.push(self.current_jet_index);
+            self.move_horizontal();
+            // println!(
+            //     "Rest pos after horizontal movement: {:?}",
+            //     self.current_rock
+            // );
+            if self.move_vertical() {
+                break;
+            }
+        }
+
+        let position = self.current_rock.get_point();
+
+        let mut sealed_rows: Vec<i128> = self
+            .current_rock
+            .get_rock_coordinates(position)
+            .iter()
+            .map(|p| p.1)
+            .collect();
+        sealed_rows.sort();
+        sealed_rows.dedup();
+        sealed_rows.retain(|y| self.is_row_full(*y));
+
+        if let Some(prune_interval) = self.prune_interval {
+            if self.rock_count.is_multiple_of(prune_interval) {
+                self.prune_unreachable_rows();
+            }
+        }
+
+        RockEvent {
+            rock_number: self.rock_count,
+            rock: self.current_rock,
+            position,
+            jet_indices,
+            max_height: self.max_y + 1,
+            sealed_rows,
+        }
+    }
+
+    pub fn is_row_full(&self, y: i128) -> bool {
+        (0..=self.max_x as i128).all(|x| self.stationary_rocks.contains_key(&Point(x, y)))
+    }
+
+    // A falling rock only ever moves left, right or down, so any empty cell which can't be
+    // reached that way from above the tower will never be touched again. Everything below the
+    // lowest reachable row (keeping the row under it to land on) is dropped. Coordinates stay
+    // absolute, `floor_y` records where the kept rows start.
+    pub fn prune_unreachable_rows(&mut self) {
+        let top_y = self.max_y + 1;
+
+        let mut min_reachable_y = top_y;
+        let mut visited: HashSet<Point> = HashSet::new();
+        let mut queue: VecDeque<Point> =
+            (0..=self.max_x as i128).map(|x| Point(x, top_y)).collect();
+
+        while let Some(point) = queue.pop_front() {
+            if point.1 > top_y || self.is_point_full(&point) || !visited.insert(point) {
+                continue;
+            }
+
+            min_reachable_y = std::cmp::min(min_reachable_y, point.1);
+
+            queue.push_back(Point(point.0 - 1, point.1));
+            queue.push_back(Point(point.0 + 1, point.1));
+            queue.push_back(Point(point.0, point.1 - 1));
+        }
+
+        let new_floor_y = min_reachable_y - 1;
+
+        if new_floor_y <= self.floor_y {
+            return;
+        }
+
+        for y in self.floor_y..new_floor_y {
+            for x in 0..=self.max_x as i128 {
+                self.stationary_rocks.remove(&Point(x, y));
+            }
+        }
+
+        self.floor_y = new_floor_y;
+    }
+
+    // Endless stream of rocks, each item is produced once the rock has come to rest
+    pub fn rock_events(&mut self) -> RockEvents<'_> {
+        RockEvents { algo: self }
+    }
+
+    // Drops every rock one by one without looking for repeats, used to cross check
+    // `get_tower_height`
+    pub fn get_tower_height_brute_force(&mut self, total_number_of_rocks: u64) -> i128 {
+        if self.prune_interval.is_none() {
+            self.prune_interval = Some(PRUNE_INTERVAL);
+        }
+
+        self.rock_events()
+            .take(total_number_of_rocks as usize)
+            .last()
+            .map_or(0, |event| event.max_height)
+    }
+
+    pub fn get_state_id(&self) -> String {
+        let empty_sots_on_top = [self.max_y; 7];
+        let empty_sots_on_top = empty_sots_on_top
+            .iter()
+            .enumerate()
+            .map(|(x, _)| {
+                // Rows under the floor have been pruned so stop looking there
+                let depth = self.max_y - std::cmp::max(self.floor_y, 0);
+                for i in 0..depth {
+                    // println!("Checking for {:?}", Point(x as i128, self.max_y - i));
+                    if self
+                        .stationary_rocks
+                        .contains_key(&Point(x as i128, self.max_y - i))
+                    {
+                        return i.to_string();
+                    }
+                }
+                depth.to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("-");
+        format!(
+            "{}-{}-{}",
+            self.current_rock_index, self.current_jet_index, empty_sots_on_top
+        )
+    }
+}
+
+pub struct RockEvents<'a> {
+    algo: &'a mut Algo,
+}
+
+impl Iterator for RockEvents<'_> {
+    type Item = RockEvent;
+
+    fn next(&mut self) -> Option<RockEvent> {
+        Some(self.algo.spawn_rock_and_run_till_stationary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_jet_pattern;
+
+    fn get_sample() -> Vec<u8> {
+        parse_jet_pattern(include_str!("../input/sample-input.txt")).unwrap()
+    }
+
+    #[test]
+    fn cycle_skipping_matches_puzzle_answers() {
+        assert_eq!(Algo::new(get_sample()).get_tower_height(2022, false), 3068);
+        assert_eq!(
+            Algo::new(get_sample()).get_tower_height(1_000_000_000_000, false),
+            1_514_285_714_288
+        );
+    }
+
+    #[test]
+    fn brute_force_matches_cycle_skipping() {
+        for rocks in [1, 5, 2022, 10_000, 100_000] {
+            assert_eq!(
+                Algo::new(get_sample()).get_tower_height_brute_force(rocks),
+                Algo::new(get_sample()).get_tower_height(rocks, false),
+                "{} rocks",
+                rocks
+            );
+        }
+    }
+
+    #[test]
+    fn pruning_keeps_the_tower_bounded() {
+        let mut algo = Algo::new(get_sample());
+        algo.get_tower_height_brute_force(100_000);
+
+        assert!(algo.floor_y > 0);
+        assert!(
+            algo.stationary_rocks.len() < 1_000,
+            "{} cells kept",
+            algo.stationary_rocks.len()
+        );
+    }
+}
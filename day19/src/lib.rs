@@ -1,5 +1,13 @@
 mod parser;
 mod part1;
 mod part2;
+mod plan;
+mod scoring;
+mod solver;
 
-pub use parser::Problem;
+pub use parser::{
+    parse_blueprints, BluePrint, BlueprintParseError, Problem, Recipe, Resources, RobotCount,
+};
+pub use plan::{BuildPlan, PlanStep};
+pub use scoring::{get_max_geodes_per_blue_print, Aggregate, BlueprintSelection, Score, Scoring};
+pub use solver::{search, search_with_budget, SearchBudget, SearchResult, SearchStats};
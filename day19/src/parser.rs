@@ -1,222 +1,394 @@
+use std::fmt;
 use utils::{
     nom::{
-        branch::permutation,
         bytes::complete::tag,
-        character::complete::space0,
-        combinator::{all_consuming, map, opt},
-        multi::separated_list0,
-        sequence::tuple,
+        character::complete::{alpha1, multispace0, multispace1},
+        combinator::all_consuming,
+        multi::{many1, separated_list1},
+        sequence::{separated_pair, tuple},
         IResult,
     },
     parser::{parse_number_u32, parse_number_u8, ParseInput},
 };
 
+// Resource the search maximises, falls back to the last resource if the blueprint has none
+pub const TARGET_RESOURCE: &str = "geode";
+// Robot that is available from the start, falls back to the first robot listed
+pub const STARTING_ROBOT: &str = "ore";
+
 #[derive(Default)]
 pub struct Problem {
-    pub time: u8,
+    // Minutes the search runs for, set when parsing
+    pub time: u32,
+    // Used instead of the puzzle's 24 and 32 minutes when set, to try longer horizons
+    pub time_override: Option<u32>,
 }
 
 impl ParseInput for Problem {
     type ParsedType = ParsedOutput;
     fn parse(&mut self, input: String, part2: bool) -> ParsedOutput {
-        let mut blueprints = Vec::new();
+        self.time = self.time_override.unwrap_or(if part2 { 32 } else { 24 });
 
-        self.time = if part2 { 32 } else { 24 };
+        parse_blueprints(&input).unwrap_or_else(|err| panic!("{}", err))
+    }
+}
 
-        input.lines().for_each(|l| {
-            let _ = map(all_consuming(parse_line), |cube| {
-                blueprints.push(cube);
-            })(l);
-        });
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlueprintParseError {
+    // 1 based position of the blueprint in the input
+    pub position: usize,
+    // Id of the blueprint, if the parser got far enough to read it
+    pub blueprint_id: Option<u8>,
+    // 1 based line the blueprint starts on
+    pub line: usize,
+    // Start of the text the parser could not make sense of
+    pub remaining: String,
+}
 
-        blueprints
+impl fmt::Display for BlueprintParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.blueprint_id {
+            Some(blueprint_id) => write!(f, "Failed to parse blueprint {}", blueprint_id)?,
+            None => write!(f, "Failed to parse blueprint number {}", self.position)?,
+        }
+        write!(f, " starting on line {}", self.line)?;
+        if !self.remaining.is_empty() {
+            write!(f, ", unexpected input at {:?}", self.remaining)?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Resources {
-    pub ore: u32,
-    pub clay: u32,
-    pub obsidian: u32,
-    pub geode: u32,
+impl std::error::Error for BlueprintParseError {}
+
+// Blueprints can be written on a single line or spread over several lines, with any whitespace
+// between the words. The input is split on the `Blueprint` keyword and every piece has to parse
+// as one blueprint.
+pub fn parse_blueprints(input: &str) -> Result<Vec<BluePrint>, BlueprintParseError> {
+    let mut starts: Vec<usize> = input.match_indices("Blueprint").map(|(i, _)| i).collect();
+
+    // Anything before the first blueprint is reported as a blueprint that couldn't be parsed
+    if !input[..starts.first().copied().unwrap_or(input.len())]
+        .trim()
+        .is_empty()
+    {
+        starts.insert(0, 0);
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(input.len());
+            let text = &input[*start..end];
+
+            all_consuming(parse_blueprint)(text)
+                .map(|(_, blueprint)| blueprint)
+                .map_err(|err| {
+                    let remaining = match err {
+                        utils::nom::Err::Error(e) | utils::nom::Err::Failure(e) => e.input,
+                        utils::nom::Err::Incomplete(_) => "",
+                    };
+                    BlueprintParseError {
+                        position: i + 1,
+                        blueprint_id: parse_blueprint_id(text).ok().map(|(_, id)| id),
+                        line: input[..*start].matches('\n').count() + 1,
+                        remaining: remaining.trim().chars().take(40).collect(),
+                    }
+                })
+        })
+        .collect()
+}
+
+// Amount of every resource, indexed by the blueprint's resource ids
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Resources(pub Vec<u64>);
+
+// Number of robots collecting every resource, indexed by the blueprint's resource ids
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RobotCount(pub Vec<u32>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Recipe {
+    // Resource the robot collects
+    pub robot: usize,
+    pub cost: Resources,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+// Robot name and its cost as (amount, resource name) pairs, as written in the blueprint
+pub type RobotCost<'a> = (&'a str, Vec<(u32, &'a str)>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BluePrint {
-    pub ore_robot_cost: Resources,
-    pub clay_robot_cost: Resources,
-    pub obsidian_robot_cost: Resources,
-    pub geode_robot_cost: Resources,
     pub blueprint_id: u8,
+    // Resources in the order they first show up, robots first and then costs
+    pub resource_names: Vec<String>,
+    pub recipes: Vec<Recipe>,
+    pub target: usize,
+    pub starting_robot: usize,
 }
 
 impl BluePrint {
+    pub fn new(blueprint_id: u8, robot_costs: Vec<RobotCost<'_>>) -> BluePrint {
+        let mut resource_names: Vec<String> = vec![];
+
+        robot_costs
+            .iter()
+            .map(|(robot, _)| *robot)
+            .chain(
+                robot_costs
+                    .iter()
+                    .flat_map(|(_, cost)| cost.iter().map(|(_, resource)| *resource)),
+            )
+            .for_each(|name| {
+                if !resource_names.iter().any(|n| n == name) {
+                    resource_names.push(name.to_string());
+                }
+            });
+
+        let get_resource_id = |name: &str| resource_names.iter().position(|n| n == name);
+
+        let recipes = robot_costs
+            .iter()
+            .map(|(robot, cost)| {
+                let mut resources = Resources::empty(resource_names.len());
+                cost.iter().for_each(|(amount, resource)| {
+                    resources.0[get_resource_id(resource).unwrap()] += *amount as u64;
+                });
+                Recipe {
+                    robot: get_resource_id(robot).unwrap(),
+                    cost: resources,
+                }
+            })
+            .collect();
+
+        let target = get_resource_id(TARGET_RESOURCE).unwrap_or(resource_names.len() - 1);
+        let starting_robot = get_resource_id(STARTING_ROBOT).unwrap_or(0);
+
+        BluePrint {
+            blueprint_id,
+            resource_names,
+            recipes,
+            target,
+            starting_robot,
+        }
+    }
+
+    pub fn get_resource_count(&self) -> usize {
+        self.resource_names.len()
+    }
+
+    // No point in having more robots of a kind than can be spent in a single minute, the target
+    // resource is never spent on so it has no limit
     pub fn get_max_robots(&self) -> RobotCount {
-        let mut robot_count = RobotCount {
-            clay: 0,
-            geode: 0,
-            obsidian: 0,
-            ore: 0,
-        };
-
-        robot_count.ore = std::cmp::max(robot_count.ore as u32, self.ore_robot_cost.ore) as u8;
-        robot_count.ore = std::cmp::max(robot_count.ore as u32, self.clay_robot_cost.ore) as u8;
-        robot_count.ore = std::cmp::max(robot_count.ore as u32, self.obsidian_robot_cost.ore) as u8;
-        robot_count.ore = std::cmp::max(robot_count.ore as u32, self.geode_robot_cost.ore) as u8;
-
-        robot_count.clay = std::cmp::max(robot_count.clay as u32, self.ore_robot_cost.clay) as u8;
-        robot_count.clay = std::cmp::max(robot_count.clay as u32, self.clay_robot_cost.clay) as u8;
-        robot_count.clay =
-            std::cmp::max(robot_count.clay as u32, self.obsidian_robot_cost.clay) as u8;
-        robot_count.clay = std::cmp::max(robot_count.clay as u32, self.geode_robot_cost.clay) as u8;
-
-        robot_count.obsidian =
-            std::cmp::max(robot_count.obsidian as u32, self.ore_robot_cost.obsidian) as u8;
-        robot_count.obsidian =
-            std::cmp::max(robot_count.obsidian as u32, self.clay_robot_cost.obsidian) as u8;
-        robot_count.obsidian = std::cmp::max(
-            robot_count.obsidian as u32,
-            self.obsidian_robot_cost.obsidian,
-        ) as u8;
-        robot_count.obsidian =
-            std::cmp::max(robot_count.obsidian as u32, self.geode_robot_cost.obsidian) as u8;
-
-        robot_count.geode = u8::MAX;
+        let mut robot_count = RobotCount(vec![0; self.get_resource_count()]);
+
+        self.recipes.iter().for_each(|recipe| {
+            recipe.cost.0.iter().enumerate().for_each(|(i, cost)| {
+                robot_count.0[i] = std::cmp::max(robot_count.0[i], *cost as u32);
+            });
+        });
+
+        robot_count.0[self.target] = u32::MAX;
 
         robot_count
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-pub struct RobotCount {
-    pub ore: u8,
-    pub clay: u8,
-    pub obsidian: u8,
-    pub geode: u8,
+    pub fn get_initial_robots(&self) -> RobotCount {
+        let mut robot_count = RobotCount(vec![0; self.get_resource_count()]);
+        robot_count.0[self.starting_robot] = 1;
+        robot_count
+    }
 }
 
 impl RobotCount {
-    pub fn init() -> RobotCount {
-        RobotCount {
-            ore: 1,
-            clay: 0,
-            obsidian: 0,
-            geode: 0,
-        }
+    pub fn get(&self, resource: usize) -> u32 {
+        self.0[resource]
     }
-}
-
-pub type ParsedOutput = Vec<BluePrint>;
 
-fn parse_blueprint_id(input: &str) -> IResult<&str, u8> {
-    let (input, (_, blueprint_id, _)) =
-        tuple((tag("Blueprint "), parse_number_u8, tag(":")))(input)?;
-    Ok((input, blueprint_id))
+    pub fn add(&mut self, resource: usize) {
+        self.0[resource] += 1;
+    }
 }
 
-fn parse_ore_cost(input: &str) -> IResult<&str, u32> {
-    // costs 2 ore
-    let (input, (ore_cost, _)) = tuple((parse_number_u32, tag(" ore")))(input)?;
-    Ok((input, ore_cost))
-}
+impl Resources {
+    pub fn empty(resource_count: usize) -> Resources {
+        Resources(vec![0; resource_count])
+    }
 
-fn parse_clay_cost(input: &str) -> IResult<&str, u32> {
-    // costs 2 ore
-    let (input, (ore_cost, _)) = tuple((parse_number_u32, tag(" clay")))(input)?;
-    Ok((input, ore_cost))
-}
+    pub fn get(&self, resource: usize) -> u64 {
+        self.0[resource]
+    }
 
-fn parse_obsidian_cost(input: &str) -> IResult<&str, u32> {
-    // costs 2 ore
-    let (input, (ore_cost, _)) = tuple((parse_number_u32, tag(" obsidian")))(input)?;
-    Ok((input, ore_cost))
-}
+    pub fn can_afford(&self, cost: &Resources) -> bool {
+        self.0.iter().zip(cost.0.iter()).all(|(a, c)| c <= a)
+    }
 
-fn parse_cost(input: &str) -> IResult<&str, Resources> {
-    // costs 2 ore
-    // costs 3 ore and 14 clay
-    let (input, costs) = separated_list0(
-        tag(" and "),
-        permutation((
-            opt(parse_ore_cost),
-            opt(parse_clay_cost),
-            opt(parse_obsidian_cost),
-        )),
-    )(input)?;
-
-    let mut resource_cost = Resources {
-        ore: 0,
-        clay: 0,
-        obsidian: 0,
-        geode: 0,
-    };
-
-    costs.iter().for_each(|cost| {
-        if let Some(ore_cost) = cost.0 {
-            resource_cost.ore = ore_cost;
-        }
-        if let Some(clay_cost) = cost.1 {
-            resource_cost.clay = clay_cost;
-        }
-        if let Some(obsidian_cost) = cost.2 {
-            resource_cost.obsidian = obsidian_cost;
-        }
-    });
+    pub fn spend(&mut self, cost: &Resources) {
+        self.0
+            .iter_mut()
+            .zip(cost.0.iter())
+            .for_each(|(a, c)| *a -= c);
+    }
 
-    Ok((input, resource_cost))
+    // Adds what the robots collect in `minutes` minutes
+    pub fn collect(&mut self, robot_count: &RobotCount, minutes: u32) {
+        self.0
+            .iter_mut()
+            .zip(robot_count.0.iter())
+            .for_each(|(a, r)| *a += *r as u64 * minutes as u64);
+    }
 }
 
-fn parse_ore_robot_cost(input: &str) -> IResult<&str, Resources> {
-    // Each ore robot costs 2 ore.
-    let (input, (_, _, robot_cost, _)) =
-        tuple((space0, tag("Each ore robot costs "), parse_cost, tag(".")))(input)?;
-    Ok((input, robot_cost))
+pub type ParsedOutput = Vec<BluePrint>;
+
+fn parse_blueprint_id(input: &str) -> IResult<&str, u8> {
+    let (input, (_, _, blueprint_id, _, _)) = tuple((
+        tag("Blueprint"),
+        multispace1,
+        parse_number_u8,
+        multispace0,
+        tag(":"),
+    ))(input)?;
+    Ok((input, blueprint_id))
 }
 
-fn parse_clay_robot_cost(input: &str) -> IResult<&str, Resources> {
-    // Each clay robot costs 2 ore.
-    let (input, (_, _, robot_cost, _)) =
-        tuple((space0, tag("Each clay robot costs "), parse_cost, tag(".")))(input)?;
-    Ok((input, robot_cost))
+fn parse_cost(input: &str) -> IResult<&str, Vec<(u32, &str)>> {
+    // 2 ore
+    // 3 ore and 14 clay
+    separated_list1(
+        tuple((multispace1, tag("and"), multispace1)),
+        separated_pair(parse_number_u32, multispace1, alpha1),
+    )(input)
 }
 
-fn parse_obsidian_robot_cost(input: &str) -> IResult<&str, Resources> {
-    // Each obsidian robot costs 2 ore.
-    let (input, (_, _, robot_cost, _)) = tuple((
-        space0,
-        tag("Each obsidian robot costs "),
+fn parse_robot_cost(input: &str) -> IResult<&str, RobotCost<'_>> {
+    // Each ore robot costs 2 ore.
+    let (input, (_, _, _, robot, _, _, _, _, _, robot_cost, _, _)) = tuple((
+        multispace0,
+        tag("Each"),
+        multispace1,
+        alpha1,
+        multispace1,
+        tag("robot"),
+        multispace1,
+        tag("costs"),
+        multispace1,
         parse_cost,
+        multispace0,
         tag("."),
     ))(input)?;
-    Ok((input, robot_cost))
-}
-
-fn parse_geode_robot_cost(input: &str) -> IResult<&str, Resources> {
-    // Each geode robot costs 2 ore.
-    let (input, (_, _, robot_cost, _)) =
-        tuple((space0, tag("Each geode robot costs "), parse_cost, tag(".")))(input)?;
-    Ok((input, robot_cost))
+    Ok((input, (robot, robot_cost)))
 }
 
-fn parse_line(input: &str) -> IResult<&str, BluePrint> {
+fn parse_blueprint(input: &str) -> IResult<&str, BluePrint> {
     // Blueprint 3: Each ore robot costs 2 ore. Each clay robot costs 2 ore. Each obsidian robot costs 2 ore and 20 clay. Each geode robot costs 2 ore and 14 obsidian.
+    //
+    // Blueprint 3:
+    //   Each ore robot costs 2 ore.
+    //   Each clay robot costs 2 ore.
+    //   ...
     let (input, blueprint_id) = parse_blueprint_id(input)?;
 
-    let (input, (ore_robot_cost, clay_robot_cost, obsidian_robot_cost, geode_robot_cost)) =
-        permutation((
-            parse_ore_robot_cost,
-            parse_clay_robot_cost,
-            parse_obsidian_robot_cost,
-            parse_geode_robot_cost,
-        ))(input)?;
-
-    let blueprint = BluePrint {
-        ore_robot_cost,
-        clay_robot_cost,
-        obsidian_robot_cost,
-        geode_robot_cost,
-        blueprint_id,
-    };
-
-    Ok((input, blueprint))
+    let (input, robot_costs) = many1(parse_robot_cost)(input)?;
+
+    let (input, _) = multispace0(input)?;
+
+    Ok((input, BluePrint::new(blueprint_id, robot_costs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    const SAMPLE: &str = include_str!("../input/sample-input.txt");
+
+    // Cost of every robot by name, so blueprints that list things in another order compare equal
+    fn get_costs(blueprint: &BluePrint) -> BTreeMap<&str, BTreeMap<&str, u64>> {
+        blueprint
+            .recipes
+            .iter()
+            .map(|recipe| {
+                let cost = recipe
+                    .cost
+                    .0
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, amount)| **amount > 0)
+                    .map(|(i, amount)| (blueprint.resource_names[i].as_str(), *amount))
+                    .collect();
+                (blueprint.resource_names[recipe.robot].as_str(), cost)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn multi_line_blueprints_match_the_single_line_sample() {
+        let input = "Blueprint 1:
+  Each ore robot costs 4 ore.
+  Each clay robot costs 2 ore.
+  Each obsidian robot costs 3 ore and 14 clay.
+  Each geode robot costs 2 ore and 7 obsidian.
+
+Blueprint 2:
+  Each ore robot costs 2 ore.
+  Each clay robot costs 3 ore.
+  Each obsidian robot costs 3 ore and 8 clay.
+  Each geode robot costs 3 ore and 12 obsidian.
+";
+        assert_eq!(
+            parse_blueprints(input).unwrap(),
+            parse_blueprints(SAMPLE).unwrap()
+        );
+    }
+
+    #[test]
+    fn robots_and_costs_can_come_in_any_order() {
+        let input = "Blueprint 1:\tEach geode robot costs 7 obsidian and 2 ore.
+            Each clay robot costs 2   ore.  Each obsidian robot costs 14 clay and 3 ore.
+            Each ore robot costs 4 ore.";
+        let reordered = parse_blueprints(input).unwrap();
+        let sample = parse_blueprints(SAMPLE).unwrap();
+
+        assert_eq!(reordered.len(), 1);
+        assert_eq!(get_costs(&reordered[0]), get_costs(&sample[0]));
+        assert_eq!(reordered[0].resource_names[reordered[0].target], "geode");
+        assert_eq!(
+            reordered[0].resource_names[reordered[0].starting_robot],
+            "ore"
+        );
+    }
+
+    #[test]
+    fn malformed_blueprint_is_reported_with_its_position() {
+        let input = SAMPLE.replace(
+            "Each clay robot costs 3 ore.",
+            "Each clay robot costs three ore.",
+        );
+        let err = parse_blueprints(&format!("\n{}", input)).unwrap_err();
+
+        assert_eq!(
+            err,
+            BlueprintParseError {
+                position: 2,
+                blueprint_id: Some(2),
+                line: 3,
+                remaining: "Each clay robot costs three ore. Each ob".to_string(),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse blueprint 2 starting on line 3, unexpected input at \
+             \"Each clay robot costs three ore. Each ob\""
+        );
+    }
+
+    #[test]
+    fn text_before_the_first_blueprint_is_reported() {
+        let err = parse_blueprints(&format!("Blueprints:\n{}", SAMPLE)).unwrap_err();
+        assert_eq!(err.position, 1);
+        assert_eq!(err.blueprint_id, None);
+        assert_eq!(err.line, 1);
+    }
 }
@@ -0,0 +1,213 @@
+use crate::parser::{BluePrint, Recipe, Resources, RobotCount, TARGET_RESOURCE};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct PlanStep {
+    pub minute: u32,
+    // Robot started this minute
+    pub built: Option<Recipe>,
+    // Robots that were collecting during this minute
+    pub collecting_robots: RobotCount,
+    // State at the end of the minute
+    pub resources: Resources,
+    pub robot_count: RobotCount,
+}
+
+#[derive(Debug, Clone)]
+pub struct BuildPlan {
+    pub resource_names: Vec<String>,
+    pub target: usize,
+    pub steps: Vec<PlanStep>,
+}
+
+impl BuildPlan {
+    // Replays the build order minute by minute
+    pub fn new(blueprint: &BluePrint, time: u32, build_order: Vec<(u32, usize)>) -> BuildPlan {
+        let mut resources = Resources::empty(blueprint.get_resource_count());
+        let mut robot_count = blueprint.get_initial_robots();
+        let mut build_order = build_order.into_iter().peekable();
+
+        let steps = (1..=time)
+            .map(|minute| {
+                let built = build_order
+                    .next_if(|(build_minute, _)| *build_minute == minute)
+                    .map(|(_, recipe_index)| blueprint.recipes[recipe_index].clone());
+
+                let collecting_robots = robot_count.clone();
+
+                if let Some(recipe) = &built {
+                    resources.spend(&recipe.cost);
+                    resources.collect(&collecting_robots, 1);
+                    robot_count.add(recipe.robot);
+                } else {
+                    resources.collect(&collecting_robots, 1);
+                }
+
+                PlanStep {
+                    minute,
+                    built,
+                    collecting_robots,
+                    resources: resources.clone(),
+                    robot_count: robot_count.clone(),
+                }
+            })
+            .collect();
+
+        BuildPlan {
+            resource_names: blueprint.resource_names.clone(),
+            target: blueprint.target,
+            steps,
+        }
+    }
+
+    pub fn get_geodes(&self) -> u64 {
+        self.steps
+            .last()
+            .map_or(0, |step| step.resources.get(self.target))
+    }
+
+    fn get_robot_description(&self, resource: usize) -> String {
+        if self.resource_names[resource] == TARGET_RESOURCE {
+            return "geode-cracking robot".to_string();
+        }
+        format!("{}-collecting robot", self.resource_names[resource])
+    }
+
+    fn get_cost_description(&self, cost: &Resources) -> String {
+        cost.0
+            .iter()
+            .enumerate()
+            .filter(|(_, amount)| **amount > 0)
+            .map(|(resource, amount)| format!("{} {}", amount, self.resource_names[resource]))
+            .collect::<Vec<String>>()
+            .join(" and ")
+    }
+}
+
+fn plural(count: u64, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        singular.to_string()
+    } else {
+        plural.to_string()
+    }
+}
+
+// Same narrative the puzzle uses to walk through the example
+impl fmt::Display for BuildPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "== Minute {} ==", step.minute)?;
+
+            if let Some(recipe) = &step.built {
+                let description = self.get_robot_description(recipe.robot);
+                let article = if description.starts_with(['a', 'e', 'i', 'o', 'u']) {
+                    "an"
+                } else {
+                    "a"
+                };
+                writeln!(
+                    f,
+                    "Spend {} to start building {} {}.",
+                    self.get_cost_description(&recipe.cost),
+                    article,
+                    description
+                )?;
+            }
+
+            for (resource, name) in self.resource_names.iter().enumerate() {
+                let robots = step.collecting_robots.get(resource) as u64;
+                if robots == 0 {
+                    continue;
+                }
+
+                let total = step.resources.get(resource);
+                let description = self.get_robot_description(resource);
+
+                if name == TARGET_RESOURCE {
+                    writeln!(
+                        f,
+                        "{} {} {} {}; you now have {} open {}.",
+                        robots,
+                        plural(robots, &description, &format!("{}s", description)),
+                        plural(robots, "cracks", "crack"),
+                        plural(robots, "1 geode", &format!("{} geodes", robots)),
+                        total,
+                        plural(total, "geode", "geodes")
+                    )?;
+                } else {
+                    writeln!(
+                        f,
+                        "{} {} {} {} {}; you now have {} {}.",
+                        robots,
+                        plural(robots, &description, &format!("{}s", description)),
+                        plural(robots, "collects", "collect"),
+                        robots,
+                        name,
+                        total,
+                        name
+                    )?;
+                }
+            }
+
+            if let Some(recipe) = &step.built {
+                writeln!(
+                    f,
+                    "The new {} is ready; you now have {} of them.",
+                    self.get_robot_description(recipe.robot),
+                    step.robot_count.get(recipe.robot)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_blueprints;
+    use crate::solver::search;
+
+    fn get_sample() -> Vec<BluePrint> {
+        parse_blueprints(include_str!("../input/sample-input.txt")).unwrap()
+    }
+
+    // The build order the puzzle walks through for the first blueprint: clay robots in minutes
+    // 3, 5, 7 and 12, obsidian robots in 11 and 15 and geode robots in 18 and 21
+    #[test]
+    fn narrative_matches_the_puzzle_example() {
+        let blueprint = &get_sample()[0];
+        let (clay, obsidian, geode) = (1, 2, 3);
+        let plan = BuildPlan::new(
+            blueprint,
+            24,
+            vec![
+                (3, clay),
+                (5, clay),
+                (7, clay),
+                (11, obsidian),
+                (12, clay),
+                (15, obsidian),
+                (18, geode),
+                (21, geode),
+            ],
+        );
+
+        assert_eq!(plan.get_geodes(), 9);
+        assert_eq!(plan.to_string(), include_str!("../input/sample-plan.txt"));
+    }
+
+    #[test]
+    fn search_plan_opens_the_best_number_of_geodes() {
+        for (blueprint, expected) in get_sample().iter().zip([9, 12]) {
+            let result = search(blueprint, 24);
+            assert_eq!(result.max_geodes, expected);
+            assert_eq!(result.plan.steps.len(), 24);
+            assert_eq!(result.plan.get_geodes(), result.max_geodes);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::parser::BluePrint;
+use crate::solver::{search_with_budget, SearchBudget, SearchResult};
+use rayon::prelude::*;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlueprintSelection {
+    All,
+    Ids(Vec<u8>),
+    IdRange(RangeInclusive<u8>),
+    // The first n blueprints in input order
+    First(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    // Sum of blueprint id * max geodes
+    QualitySum,
+    // Product of the max geodes
+    Product,
+    // Largest max geodes of any blueprint
+    Max,
+    // Max geodes, quality and search statistics of every blueprint
+    Table,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scoring {
+    pub selection: BlueprintSelection,
+    pub time: u32,
+    pub aggregate: Aggregate,
+    pub budget: SearchBudget,
+}
+
+#[derive(Debug, Clone)]
+pub enum Score {
+    Value(u64),
+    Table(Vec<(u8, SearchResult)>),
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Value(value) => write!(f, "{}", value),
+            Score::Table(rows) => {
+                for (i, (blueprint_id, result)) in rows.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(
+                        f,
+                        "Blueprint {}: {} geodes, quality level {}",
+                        blueprint_id,
+                        result.max_geodes,
+                        *blueprint_id as u64 * result.max_geodes
+                    )?;
+                    if !result.complete {
+                        write!(f, " (out of budget, at most {})", result.upper_bound)?;
+                    }
+                    write!(f, ", {}", result.stats)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl BlueprintSelection {
+    pub fn select<'a>(&self, blueprints: &'a [BluePrint]) -> Vec<&'a BluePrint> {
+        match self {
+            BlueprintSelection::All => blueprints.iter().collect(),
+            BlueprintSelection::Ids(ids) => blueprints
+                .iter()
+                .filter(|b| ids.contains(&b.blueprint_id))
+                .collect(),
+            BlueprintSelection::IdRange(range) => blueprints
+                .iter()
+                .filter(|b| range.contains(&b.blueprint_id))
+                .collect(),
+            BlueprintSelection::First(n) => blueprints.iter().take(*n).collect(),
+        }
+    }
+}
+
+impl Scoring {
+    pub fn score(&self, blueprints: &[BluePrint]) -> Score {
+        let results = get_max_geodes_per_blue_print(
+            self.selection.select(blueprints),
+            self.time,
+            &self.budget,
+        );
+
+        let max_geodes = results.iter().map(|(_, result)| result.max_geodes);
+
+        let value = match self.aggregate {
+            Aggregate::QualitySum => results
+                .iter()
+                .map(|(blueprint_id, result)| *blueprint_id as u64 * result.max_geodes)
+                .sum(),
+            Aggregate::Product => max_geodes.product(),
+            Aggregate::Max => max_geodes.max().unwrap_or(0),
+            Aggregate::Table => return Score::Table(results),
+        };
+
+        Score::Value(value)
+    }
+}
+
+pub fn get_max_geodes_per_blue_print(
+    blueprints: Vec<&BluePrint>,
+    time: u32,
+    budget: &SearchBudget,
+) -> Vec<(u8, SearchResult)> {
+    blueprints
+        .par_iter()
+        .map(|b| (b.blueprint_id, search_with_budget(b, time, budget)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_blueprints;
+
+    // The sample's blueprints open 9 and 12 geodes in 24 minutes
+    fn score(selection: BlueprintSelection, aggregate: Aggregate) -> Score {
+        let blueprints = parse_blueprints(include_str!("../input/sample-input.txt")).unwrap();
+        Scoring {
+            selection,
+            time: 24,
+            aggregate,
+            budget: SearchBudget::default(),
+        }
+        .score(&blueprints)
+    }
+
+    fn value(selection: BlueprintSelection, aggregate: Aggregate) -> u64 {
+        match score(selection, aggregate) {
+            Score::Value(value) => value,
+            Score::Table(_) => panic!("Expected a single value"),
+        }
+    }
+
+    #[test]
+    fn quality_sum_of_all_or_some_blueprints() {
+        assert_eq!(value(BlueprintSelection::All, Aggregate::QualitySum), 33);
+        assert_eq!(
+            value(BlueprintSelection::Ids(vec![2]), Aggregate::QualitySum),
+            24
+        );
+        assert_eq!(
+            value(BlueprintSelection::IdRange(1..=1), Aggregate::QualitySum),
+            9
+        );
+        assert_eq!(
+            value(BlueprintSelection::First(1), Aggregate::QualitySum),
+            9
+        );
+        // Ids that aren't in the input select nothing
+        assert_eq!(
+            value(BlueprintSelection::Ids(vec![3, 7]), Aggregate::QualitySum),
+            0
+        );
+    }
+
+    #[test]
+    fn product_and_max_of_selections() {
+        assert_eq!(
+            value(BlueprintSelection::Ids(vec![1, 2]), Aggregate::Product),
+            108
+        );
+        assert_eq!(
+            value(BlueprintSelection::IdRange(2..=5), Aggregate::Max),
+            12
+        );
+        assert_eq!(value(BlueprintSelection::First(5), Aggregate::Product), 108);
+        assert_eq!(value(BlueprintSelection::First(0), Aggregate::Max), 0);
+    }
+
+    #[test]
+    fn max_of_the_selected_blueprints() {
+        assert_eq!(value(BlueprintSelection::All, Aggregate::Max), 12);
+        assert_eq!(value(BlueprintSelection::Ids(vec![1]), Aggregate::Max), 9);
+    }
+
+    #[test]
+    fn table_has_a_row_per_blueprint() {
+        let Score::Table(rows) = score(BlueprintSelection::All, Aggregate::Table) else {
+            panic!("Expected a table");
+        };
+        let summary: Vec<(u8, u64, u64, bool)> = rows
+            .iter()
+            .map(|(id, result)| (*id, result.max_geodes, result.upper_bound, result.complete))
+            .collect();
+        assert_eq!(summary, vec![(1, 9, 9, true), (2, 12, 12, true)]);
+
+        // Statistics depend on timing, only the start of every row is fixed
+        let table = Score::Table(rows).to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("Blueprint 1: 9 geodes, quality level 9, "));
+        assert!(lines[1].starts_with("Blueprint 2: 12 geodes, quality level 24, "));
+    }
+}
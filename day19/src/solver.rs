@@ -0,0 +1,484 @@
+use crate::parser::{BluePrint, Recipe, Resources, RobotCount};
+use crate::plan::BuildPlan;
+use std::collections::{BinaryHeap, HashSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// Hashed as a whole to find repeated states, so unlike a packed key it can't collide however
+// large the counts get
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct State {
+    resources: Resources,
+    robot_count: RobotCount,
+    time_left: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    state: State,
+    // Index of the node this one was reached from and the recipe built to get here
+    parent: Option<(usize, usize)>,
+}
+
+// Limits for a single blueprint, the search stops with what it has found so far once either
+// is reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchBudget {
+    pub max_states: Option<u64>,
+    pub time_limit: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    // States taken off the queue and expanded
+    pub states_visited: u64,
+    // States reached again by another build order
+    pub cache_hits: u64,
+    // States dropped because their upper bound can't beat the best result
+    pub bound_prunes: u64,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    // Best found, this is the answer when `complete` is set and a lower bound otherwise
+    pub max_geodes: u64,
+    // Nothing can do better than this, equal to `max_geodes` when `complete` is set
+    pub upper_bound: u64,
+    // False when the budget ran out before the search finished
+    pub complete: bool,
+    pub plan: BuildPlan,
+    pub stats: SearchStats,
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} states visited, {} cache hits, {} bound prunes in {:?}",
+            self.states_visited, self.cache_hits, self.bound_prunes, self.elapsed
+        )
+    }
+}
+
+impl SearchBudget {
+    fn is_exceeded(&self, stats: &SearchStats, start: Instant) -> bool {
+        self.max_states
+            .is_some_and(|max_states| stats.states_visited >= max_states)
+            || self
+                .time_limit
+                .is_some_and(|time_limit| start.elapsed() >= time_limit)
+    }
+}
+
+impl State {
+    // Target resource at the end if nothing else is built from here on
+    fn get_lower_bound(&self, blueprint: &BluePrint) -> u64 {
+        self.resources.get(blueprint.target)
+            + self.robot_count.get(blueprint.target) as u64 * self.time_left as u64
+    }
+
+    // Instead of stepping through every minute, wait until the robot can be afforded and
+    // build it straight away. None when it can't be built in time to be of any use.
+    fn build_next(&self, recipe: &Recipe) -> Option<State> {
+        let wait_time = recipe
+            .cost
+            .0
+            .iter()
+            .zip(self.resources.0.iter().zip(self.robot_count.0.iter()))
+            .try_fold(0, |wait_time, (cost, (available, robots))| {
+                get_minutes_to_afford(*cost, *available, *robots).map(|t| wait_time.max(t))
+            })?;
+
+        // The robot is ready at the end of the minute it is built in, it needs at least a
+        // minute after that to collect anything
+        let build_time = wait_time + 1;
+        if build_time >= self.time_left {
+            return None;
+        }
+
+        let mut resources = self.resources.clone();
+        resources.collect(&self.robot_count, build_time);
+        resources.spend(&recipe.cost);
+
+        let mut robot_count = self.robot_count.clone();
+        robot_count.add(recipe.robot);
+
+        Some(State {
+            resources,
+            robot_count,
+            time_left: self.time_left - build_time,
+        })
+    }
+
+    // Relaxed run where every recipe has its own copy of the resources and every recipe can be
+    // built every minute. Nothing real can do better, so this never underestimates.
+    fn get_upper_bound(&self, blueprint: &BluePrint) -> u64 {
+        let mut pools = vec![self.resources.clone(); blueprint.recipes.len()];
+        let mut robot_count = self.robot_count.clone();
+        let mut target = self.resources.get(blueprint.target);
+
+        for _ in 0..self.time_left {
+            let built: Vec<bool> = blueprint
+                .recipes
+                .iter()
+                .zip(pools.iter_mut())
+                .map(|(recipe, pool)| {
+                    let can_afford = pool.can_afford(&recipe.cost);
+                    if can_afford {
+                        pool.spend(&recipe.cost);
+                    }
+                    can_afford
+                })
+                .collect();
+
+            pools
+                .iter_mut()
+                .for_each(|pool| pool.collect(&robot_count, 1));
+            target += robot_count.get(blueprint.target) as u64;
+
+            blueprint
+                .recipes
+                .iter()
+                .zip(built)
+                .filter(|(_, built)| *built)
+                .for_each(|(recipe, _)| robot_count.add(recipe.robot));
+        }
+
+        target
+    }
+}
+
+fn get_minutes_to_afford(cost: u64, available: u64, robots: u32) -> Option<u32> {
+    if cost <= available {
+        return Some(0);
+    }
+    if robots == 0 {
+        return None;
+    }
+    u32::try_from((cost - available).div_ceil(robots as u64)).ok()
+}
+
+// Recipes for the target resource first, then the rest in reverse order of the resources, so
+// the most valuable robots are tried first
+fn get_search_order(blueprint: &BluePrint) -> Vec<usize> {
+    let mut search_order: Vec<usize> = (0..blueprint.recipes.len()).collect();
+    search_order.sort_by_key(|i| {
+        let robot = blueprint.recipes[*i].robot;
+        (robot != blueprint.target, std::cmp::Reverse(robot))
+    });
+    search_order
+}
+
+// Best first branch and bound. States are expanded in order of their upper bound, so once the
+// best remaining upper bound can't beat the best known result the search is done.
+pub fn search(blueprint: &BluePrint, time: u32) -> SearchResult {
+    search_with_budget(blueprint, time, &SearchBudget::default())
+}
+
+pub fn search_with_budget(blueprint: &BluePrint, time: u32, budget: &SearchBudget) -> SearchResult {
+    let start = Instant::now();
+    let mut stats = SearchStats::default();
+
+    let max_robots = blueprint.get_max_robots();
+    let search_order = get_search_order(blueprint);
+
+    let initial_state = State {
+        resources: Resources::empty(blueprint.get_resource_count()),
+        robot_count: blueprint.get_initial_robots(),
+        time_left: time,
+    };
+
+    let mut best = initial_state.get_lower_bound(blueprint);
+    let mut best_index = 0;
+
+    let mut queue: BinaryHeap<(u64, usize)> = BinaryHeap::new();
+    let mut seen: HashSet<State> = HashSet::new();
+
+    queue.push((initial_state.get_upper_bound(blueprint), 0));
+
+    let mut nodes: Vec<Node> = vec![Node {
+        state: initial_state,
+        parent: None,
+    }];
+
+    let mut remaining_upper_bound = best;
+
+    while let Some((upper_bound, index)) = queue.pop() {
+        if upper_bound <= best {
+            break;
+        }
+
+        // The queue is ordered by upper bound, so the one just taken off bounds everything left
+        if budget.is_exceeded(&stats, start) {
+            remaining_upper_bound = upper_bound;
+            break;
+        }
+
+        stats.states_visited += 1;
+
+        let state = nodes[index].state.clone();
+
+        for recipe_index in search_order.iter() {
+            let recipe = &blueprint.recipes[*recipe_index];
+
+            if state.robot_count.get(recipe.robot) >= max_robots.get(recipe.robot) {
+                continue;
+            }
+
+            let Some(next_state) = state.build_next(recipe) else {
+                continue;
+            };
+
+            if !seen.insert(next_state.clone()) {
+                stats.cache_hits += 1;
+                continue;
+            }
+
+            let lower_bound = next_state.get_lower_bound(blueprint);
+            let upper_bound = next_state.get_upper_bound(blueprint);
+
+            if lower_bound <= best && upper_bound <= best {
+                stats.bound_prunes += 1;
+                continue;
+            }
+
+            nodes.push(Node {
+                state: next_state,
+                parent: Some((index, *recipe_index)),
+            });
+
+            if lower_bound > best {
+                best = lower_bound;
+                best_index = nodes.len() - 1;
+            }
+
+            if upper_bound > best {
+                queue.push((upper_bound, nodes.len() - 1));
+            }
+        }
+    }
+
+    stats.elapsed = start.elapsed();
+
+    SearchResult {
+        max_geodes: best,
+        upper_bound: std::cmp::max(best, remaining_upper_bound),
+        complete: remaining_upper_bound <= best,
+        plan: BuildPlan::new(blueprint, time, get_build_order(&nodes, best_index, time)),
+        stats,
+    }
+}
+
+// Walks back from the best node, returns the minute each recipe was built in
+fn get_build_order(nodes: &[Node], index: usize, time: u32) -> Vec<(u32, usize)> {
+    let mut build_order = vec![];
+    let mut node = &nodes[index];
+
+    while let Some((parent_index, recipe_index)) = node.parent {
+        build_order.push((time - node.state.time_left, recipe_index));
+        node = &nodes[parent_index];
+    }
+
+    build_order.reverse();
+    build_order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_blueprints, Problem};
+    use std::collections::HashMap;
+    use utils::parser::ParseInput;
+
+    // Exhaustive search one minute at a time, trying every robot that can be afforded. Robots
+    // past what can be spent in a minute, and resources past what can be spent before the end,
+    // can't change the result, so they're capped to keep the memo small. The target robots
+    // and resource are left out of the memo as they only add geodes.
+    fn brute_force(blueprint: &BluePrint, time: u32) -> u64 {
+        let max_robots = blueprint.get_max_robots();
+        let max_costs: Vec<u64> = (0..blueprint.get_resource_count())
+            .map(|resource| {
+                blueprint
+                    .recipes
+                    .iter()
+                    .map(|recipe| recipe.cost.get(resource))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        fn visit(
+            blueprint: &BluePrint,
+            max_robots: &RobotCount,
+            max_costs: &[u64],
+            memo: &mut HashMap<(u32, Vec<u32>, Vec<u64>), u64>,
+            time_left: u32,
+            robot_count: RobotCount,
+            mut resources: Resources,
+        ) -> u64 {
+            if time_left == 0 {
+                return 0;
+            }
+
+            let target = blueprint.target;
+            for (resource, amount) in resources.0.iter_mut().enumerate() {
+                if resource != target {
+                    *amount = std::cmp::min(*amount, max_costs[resource] * time_left as u64);
+                }
+            }
+            resources.0[target] = 0;
+            let mut robots_key = robot_count.0.clone();
+            robots_key[target] = 0;
+            let key = (time_left, robots_key, resources.0.clone());
+            if let Some(value) = memo.get(&key) {
+                return *value;
+            }
+
+            let mut best = 0;
+            for recipe in blueprint.recipes.iter().map(Some).chain([None]) {
+                let mut next_robots = robot_count.clone();
+                let mut next_resources = resources.clone();
+                let mut gained = 0;
+                if let Some(recipe) = recipe {
+                    if !resources.can_afford(&recipe.cost)
+                        || robot_count.get(recipe.robot) >= max_robots.get(recipe.robot)
+                    {
+                        continue;
+                    }
+                    next_resources.spend(&recipe.cost);
+                    if recipe.robot == target {
+                        gained = time_left as u64 - 1;
+                    } else {
+                        next_robots.add(recipe.robot);
+                    }
+                }
+                next_resources.collect(&robot_count, 1);
+                next_robots.0[target] = 0;
+                best = std::cmp::max(
+                    best,
+                    gained
+                        + visit(
+                            blueprint,
+                            max_robots,
+                            max_costs,
+                            memo,
+                            time_left - 1,
+                            next_robots,
+                            next_resources,
+                        ),
+                );
+            }
+
+            memo.insert(key, best);
+            best
+        }
+
+        let initial_robots = blueprint.get_initial_robots();
+        initial_robots.get(blueprint.target) as u64 * time as u64
+            + visit(
+                blueprint,
+                &max_robots,
+                &max_costs,
+                &mut HashMap::new(),
+                time,
+                initial_robots,
+                Resources::empty(blueprint.get_resource_count()),
+            )
+    }
+
+    fn get_sample() -> Vec<BluePrint> {
+        parse_blueprints(include_str!("../input/sample-input.txt")).unwrap()
+    }
+
+    #[test]
+    fn matches_brute_force_on_the_sample() {
+        for blueprint in get_sample() {
+            for time in [1, 5, 12, 16, 20] {
+                assert_eq!(
+                    search(&blueprint, time).max_geodes,
+                    brute_force(&blueprint, time),
+                    "blueprint {} for {} minutes",
+                    blueprint.blueprint_id,
+                    time
+                );
+            }
+        }
+    }
+
+    // Cheap robots make every count go past 255 well before the end, which used to bleed into
+    // the neighbouring counts of the packed state keys
+    #[test]
+    fn matches_brute_force_past_u8_counts() {
+        let blueprints = parse_blueprints(
+            "Blueprint 1: Each ore robot costs 2 ore. Each clay robot costs 1 ore. \
+             Each geode robot costs 2 ore and 3 clay.\n\
+             Blueprint 2: Each ore robot costs 1 ore. Each geode robot costs 3 ore.",
+        )
+        .unwrap();
+
+        for blueprint in blueprints.iter() {
+            for time in [33, 40] {
+                let expected = brute_force(blueprint, time);
+                assert!(expected > 255);
+                assert_eq!(
+                    search(blueprint, time).max_geodes,
+                    expected,
+                    "blueprint {} for {} minutes",
+                    blueprint.blueprint_id,
+                    time
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn long_horizons_on_the_sample() {
+        let blueprint = &get_sample()[0];
+        assert_eq!(search(blueprint, 40).max_geodes, 158);
+        assert_eq!(search(blueprint, 60).max_geodes, 688);
+    }
+
+    // The puzzle's answers for the sample over 32 minutes are 56 and 62
+    #[test]
+    fn running_out_of_budget_keeps_valid_bounds() {
+        for (blueprint, optimum) in get_sample().iter().zip([56, 62]) {
+            assert_eq!(search(blueprint, 32).max_geodes, optimum);
+
+            for max_states in [1, 10, 100] {
+                let budget = SearchBudget {
+                    max_states: Some(max_states),
+                    time_limit: None,
+                };
+                let result = search_with_budget(blueprint, 32, &budget);
+
+                assert!(!result.complete, "{} states", max_states);
+                assert!(
+                    result.max_geodes <= optimum && optimum <= result.upper_bound,
+                    "{} <= {} <= {} with {} states",
+                    result.max_geodes,
+                    optimum,
+                    result.upper_bound,
+                    max_states
+                );
+                assert_eq!(result.stats.states_visited, max_states);
+                assert_eq!(result.plan.get_geodes(), result.max_geodes);
+            }
+        }
+    }
+
+    #[test]
+    fn time_override_is_kept_between_parts() {
+        let input = include_str!("../input/sample-input.txt").to_string();
+
+        let mut problem = Problem::default();
+        problem.parse(input.clone(), false);
+        assert_eq!(problem.time, 24);
+        problem.parse(input.clone(), true);
+        assert_eq!(problem.time, 32);
+
+        problem.time_override = Some(40);
+        problem.parse(input, true);
+        assert_eq!(problem.time, 40);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::mixer::Mixer;
+use std::fmt;
+
+// Positions after the zero the puzzle sums up
+pub const DEFAULT_OFFSETS: [usize; 3] = [1000, 2000, 3000];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroveCoordinates {
+    // Offset after the zero and the number found there
+    pub contributions: Vec<(usize, i64)>,
+}
+
+impl GroveCoordinates {
+    // Offsets wrap around, so any offset can be asked for
+    pub fn new(sequence_from_zero: &[i64], offsets: &[usize]) -> GroveCoordinates {
+        GroveCoordinates {
+            contributions: offsets
+                .iter()
+                .map(|offset| {
+                    (
+                        *offset,
+                        sequence_from_zero[offset % sequence_from_zero.len()],
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn sum(&self) -> i64 {
+        self.contributions.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl fmt::Display for GroveCoordinates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (offset, value) in self.contributions.iter() {
+            writeln!(f, "Number {} places after 0 is {}", offset, value)?;
+        }
+        write!(f, "Sum of the grove coordinates is {}", self.sum())
+    }
+}
+
+// Multiplies every number by the decryption key and mixes them `rounds` times. The result is
+// rotated so it starts at the zero, None when there is no zero to start from.
+pub fn get_mixed_sequence(numbers: &[i64], decryption_key: i64, rounds: u32) -> Option<Vec<i64>> {
+    let mut mixer = Mixer::new(
+        numbers
+            .iter()
+            .map(|number| number * decryption_key)
+            .collect(),
+    );
+
+    for _ in 0..rounds {
+        mixer.mix();
+    }
+
+    mixer.get_sequence_from_zero()
+}
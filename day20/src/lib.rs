@@ -1,5 +1,9 @@
+mod grove;
+mod mixer;
 mod parser;
 mod part1;
 mod part2;
 
-pub use parser::Problem;
+pub use grove::{get_mixed_sequence, GroveCoordinates, DEFAULT_OFFSETS};
+pub use mixer::{MixMove, MixMoves, Mixer, TRACE_SEQUENCE_LIMIT};
+pub use parser::{NumericalMessage, Problem};
@@ -0,0 +1,487 @@
+use std::fmt;
+
+// Marks a missing child or parent
+const NONE: usize = usize::MAX;
+
+// Moves only carry the whole sequence for inputs up to this size
+pub const TRACE_SEQUENCE_LIMIT: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+struct TreapNode {
+    left: usize,
+    right: usize,
+    parent: usize,
+    size: usize,
+    priority: u64,
+}
+
+// Implicit treap keyed by position. Node i always holds the i-th number of the original input,
+// so a number can be found by walking up from its node, and moving it is a split and a merge.
+// Every move takes O(log n) instead of shifting everything in between.
+pub struct Mixer {
+    nodes: Vec<TreapNode>,
+    values: Vec<i64>,
+    root: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixMove {
+    // 1 based round the move belongs to
+    pub round: u32,
+    // Position of the number in the original input
+    pub index: usize,
+    pub value: i64,
+    pub old_position: usize,
+    pub new_position: usize,
+    // Whole sequence after the move, only kept for small inputs
+    pub sequence: Option<Vec<i64>>,
+}
+
+// Makes the moves of the given number of rounds one at a time
+pub struct MixMoves<'a> {
+    mixer: &'a mut Mixer,
+    rounds: u32,
+    round: u32,
+    index: usize,
+}
+
+impl Mixer {
+    pub fn new(values: Vec<i64>) -> Mixer {
+        // Fixed seed xorshift, the priorities only have to look random to keep the tree balanced
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        let nodes = (0..values.len())
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                TreapNode {
+                    left: NONE,
+                    right: NONE,
+                    parent: NONE,
+                    size: 1,
+                    priority: seed,
+                }
+            })
+            .collect();
+
+        let mut mixer = Mixer {
+            nodes,
+            values,
+            root: NONE,
+        };
+
+        for i in 0..mixer.values.len() {
+            mixer.root = mixer.merge(mixer.root, i);
+        }
+        mixer.set_root(mixer.root);
+
+        mixer
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn get_value(&self, index: usize) -> i64 {
+        self.values[index]
+    }
+
+    // Moves every number once, in the original order
+    pub fn mix(&mut self) {
+        for index in 0..self.len() {
+            self.move_number(index);
+        }
+    }
+
+    // Same as calling `mix` `rounds` times, but every move can be looked at as it happens
+    pub fn moves(&mut self, rounds: u32) -> MixMoves<'_> {
+        MixMoves {
+            mixer: self,
+            rounds,
+            round: 0,
+            index: 0,
+        }
+    }
+
+    // Moves the number that started at `index` forward by its value, wrapping around the list.
+    // Returns the position it was taken from and the one it was put in.
+    pub fn move_number(&mut self, index: usize) -> (usize, usize) {
+        let old_position = self.position_of(index);
+
+        if self.len() <= 1 {
+            return (old_position, old_position);
+        }
+
+        // The number is taken out while moving, so it wraps around the other n - 1 numbers
+        let new_position =
+            (old_position as i64 + self.values[index]).rem_euclid(self.len() as i64 - 1) as usize;
+
+        let (before, rest) = self.split(self.root, old_position);
+        let (node, after) = self.split(rest, 1);
+        let without_node = self.merge(before, after);
+
+        let (before, after) = self.split(without_node, new_position);
+        let with_node = self.merge(before, node);
+        let root = self.merge(with_node, after);
+        self.set_root(root);
+
+        (old_position, new_position)
+    }
+
+    // Current position of the number that started at `index`
+    pub fn position_of(&self, index: usize) -> usize {
+        let mut position = self.get_size(self.nodes[index].left);
+        let mut current = index;
+
+        while self.nodes[current].parent != NONE {
+            let parent = self.nodes[current].parent;
+            if self.nodes[parent].right == current {
+                position += self.get_size(self.nodes[parent].left) + 1;
+            }
+            current = parent;
+        }
+
+        position
+    }
+
+    // Original indexes of the numbers in their current order
+    pub fn get_order(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.len());
+        let mut stack = vec![];
+        let mut current = self.root;
+
+        while current != NONE || !stack.is_empty() {
+            while current != NONE {
+                stack.push(current);
+                current = self.nodes[current].left;
+            }
+            let node = stack.pop().unwrap();
+            order.push(node);
+            current = self.nodes[node].right;
+        }
+
+        order
+    }
+
+    // Numbers in their current order
+    pub fn to_vec(&self) -> Vec<i64> {
+        self.get_order()
+            .into_iter()
+            .map(|index| self.values[index])
+            .collect()
+    }
+
+    // Numbers in their current order, rotated so the list starts at the zero
+    pub fn get_sequence_from_zero(&self) -> Option<Vec<i64>> {
+        let mut sequence = self.to_vec();
+        let index_of_0 = sequence.iter().position(|value| *value == 0)?;
+        sequence.rotate_left(index_of_0);
+        Some(sequence)
+    }
+
+    fn get_size(&self, node: usize) -> usize {
+        if node == NONE {
+            0
+        } else {
+            self.nodes[node].size
+        }
+    }
+
+    fn set_root(&mut self, root: usize) {
+        self.root = root;
+        if root != NONE {
+            self.nodes[root].parent = NONE;
+        }
+    }
+
+    // Recomputes the size of the node and points its children back at it
+    fn update(&mut self, node: usize) {
+        let TreapNode { left, right, .. } = self.nodes[node];
+        self.nodes[node].size = 1 + self.get_size(left) + self.get_size(right);
+        if left != NONE {
+            self.nodes[left].parent = node;
+        }
+        if right != NONE {
+            self.nodes[right].parent = node;
+        }
+    }
+
+    fn merge(&mut self, left: usize, right: usize) -> usize {
+        if left == NONE {
+            return right;
+        }
+        if right == NONE {
+            return left;
+        }
+
+        if self.nodes[left].priority > self.nodes[right].priority {
+            let merged = self.merge(self.nodes[left].right, right);
+            self.nodes[left].right = merged;
+            self.update(left);
+            left
+        } else {
+            let merged = self.merge(left, self.nodes[right].left);
+            self.nodes[right].left = merged;
+            self.update(right);
+            right
+        }
+    }
+
+    // Splits off the first `count` numbers
+    fn split(&mut self, node: usize, count: usize) -> (usize, usize) {
+        if node == NONE {
+            return (NONE, NONE);
+        }
+
+        let left_size = self.get_size(self.nodes[node].left);
+
+        if left_size >= count {
+            let (left, right) = self.split(self.nodes[node].left, count);
+            self.nodes[node].left = right;
+            self.update(node);
+            if left != NONE {
+                self.nodes[left].parent = NONE;
+            }
+            (left, node)
+        } else {
+            let (left, right) = self.split(self.nodes[node].right, count - left_size - 1);
+            self.nodes[node].right = left;
+            self.update(node);
+            if right != NONE {
+                self.nodes[right].parent = NONE;
+            }
+            (node, right)
+        }
+    }
+}
+
+impl Iterator for MixMoves<'_> {
+    type Item = MixMove;
+
+    fn next(&mut self) -> Option<MixMove> {
+        if self.mixer.is_empty() {
+            return None;
+        }
+
+        if self.index == self.mixer.len() {
+            self.index = 0;
+            self.round += 1;
+        }
+        if self.round >= self.rounds {
+            return None;
+        }
+
+        let index = self.index;
+        self.index += 1;
+
+        let (old_position, new_position) = self.mixer.move_number(index);
+
+        Some(MixMove {
+            round: self.round + 1,
+            index,
+            value: self.mixer.get_value(index),
+            old_position,
+            new_position,
+            sequence: (self.mixer.len() <= TRACE_SEQUENCE_LIMIT).then(|| self.mixer.to_vec()),
+        })
+    }
+}
+
+// Same wording as the puzzle's walk through the example, the sequence may be rotated compared
+// to the puzzle's as only the circular order matters
+impl fmt::Display for MixMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(sequence) = &self.sequence else {
+            return write!(
+                f,
+                "{} moves from position {} to {}",
+                self.value, self.old_position, self.new_position
+            );
+        };
+
+        let len = sequence.len();
+        if len <= 1 || self.value.rem_euclid(len as i64 - 1) == 0 {
+            writeln!(f, "{} does not move:", self.value)?;
+        } else {
+            writeln!(
+                f,
+                "{} moves between {} and {}:",
+                self.value,
+                sequence[(self.new_position + len - 1) % len],
+                sequence[(self.new_position + 1) % len]
+            )?;
+        }
+
+        write!(
+            f,
+            "{}",
+            sequence
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deliberately simple mixing to check the treap against: the list is a vector of (original
+    // index, value) pairs and every move removes the number and inserts it again. Nothing is
+    // reduced up front, the only wrapping is the single rem_euclid on the n - 1 other numbers.
+    // Returns the original indexes in their final order.
+    fn mix_naive(numbers: &[i64], decryption_key: i64, rounds: u32) -> Vec<usize> {
+        let mut list: Vec<(usize, i64)> = numbers
+            .iter()
+            .map(|number| number * decryption_key)
+            .enumerate()
+            .collect();
+
+        if list.len() <= 1 {
+            return list.iter().map(|(index, _)| *index).collect();
+        }
+
+        for _ in 0..rounds {
+            for index in 0..list.len() {
+                let position = list.iter().position(|(i, _)| *i == index).unwrap();
+                let number = list.remove(position);
+                let new_position =
+                    (position as i64 + number.1).rem_euclid(list.len() as i64) as usize;
+                list.insert(new_position, number);
+            }
+        }
+
+        list.iter().map(|(index, _)| *index).collect()
+    }
+
+    // Only the circular order matters, so both are rotated to start at the first number of the
+    // input
+    fn is_same_circular_order(a: &[usize], b: &[usize]) -> bool {
+        let rotate = |order: &[usize]| {
+            let mut order = order.to_vec();
+            if let Some(start) = order.iter().position(|index| *index == 0) {
+                order.rotate_left(start);
+            }
+            order
+        };
+        a.len() == b.len() && rotate(a) == rotate(b)
+    }
+
+    fn mix(numbers: &[i64], decryption_key: i64, rounds: u32) -> Vec<usize> {
+        let mut mixer = Mixer::new(numbers.iter().map(|n| n * decryption_key).collect());
+        for _ in 0..rounds {
+            mixer.mix();
+        }
+        mixer.get_order()
+    }
+
+    fn assert_matches_reference(numbers: &[i64], decryption_key: i64, rounds: u32) {
+        let expected = mix_naive(numbers, decryption_key, rounds);
+        let actual = mix(numbers, decryption_key, rounds);
+        assert!(
+            is_same_circular_order(&expected, &actual),
+            "Mixing {:?} {} times with key {} gave the order {:?}, expected {:?}",
+            numbers,
+            rounds,
+            decryption_key,
+            actual,
+            expected
+        );
+    }
+
+    // Rotated to start at the zero, as only the circular order matters
+    fn from_zero(sequence: &[i64]) -> Vec<i64> {
+        let mut sequence = sequence.to_vec();
+        let index_of_0 = sequence.iter().position(|value| *value == 0).unwrap();
+        sequence.rotate_left(index_of_0);
+        sequence
+    }
+
+    #[test]
+    fn moves_match_the_puzzle_example() {
+        let expected = [
+            ("1 moves between 2 and -3:", [2, 1, -3, 3, -2, 0, 4]),
+            ("2 moves between -3 and 3:", [1, -3, 2, 3, -2, 0, 4]),
+            ("-3 moves between -2 and 0:", [1, 2, 3, -2, -3, 0, 4]),
+            ("3 moves between 0 and 4:", [1, 2, -2, -3, 0, 3, 4]),
+            ("-2 moves between 4 and 1:", [1, 2, -3, 0, 3, 4, -2]),
+            ("0 does not move:", [1, 2, -3, 0, 3, 4, -2]),
+            ("4 moves between -3 and 0:", [1, 2, -3, 4, 0, 3, -2]),
+        ];
+
+        let mut mixer = Mixer::new(vec![1, 2, -3, 3, -2, 0, 4]);
+        let moves: Vec<MixMove> = mixer.moves(1).collect();
+        assert_eq!(moves.len(), expected.len());
+
+        for (mix_move, (header, sequence)) in moves.iter().zip(expected) {
+            let text = mix_move.to_string();
+            assert_eq!(text.lines().next(), Some(header));
+            assert_eq!(
+                from_zero(mix_move.sequence.as_ref().unwrap()),
+                from_zero(&sequence)
+            );
+        }
+
+        assert_eq!(
+            mixer.get_sequence_from_zero(),
+            Some(vec![0, 3, -2, 1, 2, -3, 4])
+        );
+    }
+
+    #[test]
+    fn duplicates_match_reference() {
+        assert_matches_reference(&[3, 3, 3, 0, 3, 3], 1, 1);
+        assert_matches_reference(&[1, 1, -1, -1, 0, 2, 2], 1, 3);
+    }
+
+    #[test]
+    fn negatives_match_reference() {
+        assert_matches_reference(&[-1, -7, -13, 0, -2, -100], 1, 1);
+        assert_matches_reference(&[-5, 4, -3, 0, 2, -1], 1, 2);
+    }
+
+    #[test]
+    fn large_keys_match_reference() {
+        assert_matches_reference(&[1, 2, -3, 3, -2, 0, 4], 811589153, 10);
+        assert_matches_reference(&[999_999_999, -999_999_999, 0, 5], 811589153, 3);
+    }
+
+    #[test]
+    fn tiny_inputs_match_reference() {
+        assert_matches_reference(&[0], 1, 1);
+        assert_matches_reference(&[0, 5], 7, 4);
+        assert_matches_reference(&[4, 0, -4], 1, 5);
+    }
+
+    // Short random inputs with lots of duplicates and negatives, mixed for several rounds with
+    // keys big enough to wrap around the list many times
+    #[test]
+    fn random_inputs_match_reference() {
+        let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        for _ in 0..2000 {
+            let len = 1 + next(40) as usize;
+            // A small range makes duplicates likely, a big one makes moves wrap many times
+            let range = [3, 10, 1_000, 1_000_000_000][next(4) as usize];
+            let numbers: Vec<i64> = (0..len)
+                .map(|_| next(2 * range + 1) as i64 - range as i64)
+                .collect();
+            let decryption_key = [1, 2, -7, 811589153][next(4) as usize];
+            let rounds = 1 + next(10) as u32;
+
+            assert_matches_reference(&numbers, decryption_key, rounds);
+        }
+    }
+}
@@ -1,24 +1,31 @@
+use crate::grove::DEFAULT_OFFSETS;
 use utils::{
     nom::combinator::{all_consuming, map},
     parser::{parse_number_i64, ParseInput},
 };
 
-pub type ParsedOutput = (Vec<NumericalMessage>, i64);
+pub type ParsedOutput = Vec<NumericalMessage>;
 
 #[derive(Default)]
 pub struct Problem {
     pub number_of_mixes: u8,
     pub multiplication_factor: i64,
+    // Positions after the zero that are summed up, the puzzle's offsets are used when empty
+    pub offsets: Vec<usize>,
+    // Prints the number at every offset along with the sum
+    pub print_coordinates: bool,
 }
 
 impl ParseInput for Problem {
     type ParsedType = ParsedOutput;
     fn parse(&mut self, input: String, is_part2: bool) -> ParsedOutput {
         let mut data_arr: Vec<NumericalMessage> = Vec::new();
-        let mut index_of_0 = 0;
 
         self.number_of_mixes = if is_part2 { 10 } else { 1 };
         self.multiplication_factor = if is_part2 { 811589153 } else { 1 };
+        if self.offsets.is_empty() {
+            self.offsets = DEFAULT_OFFSETS.to_vec();
+        }
 
         input.lines().enumerate().for_each(|(index, l)| {
             let _ = map(all_consuming(parse_number_i64), |data| {
@@ -26,13 +33,10 @@ impl ParseInput for Problem {
                     data,
                     index: index as i64,
                 });
-                if data == 0 {
-                    index_of_0 = index as i64;
-                }
             })(l);
         });
 
-        (data_arr, index_of_0)
+        data_arr
     }
 }
 
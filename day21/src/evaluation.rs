@@ -0,0 +1,255 @@
+use crate::graph::{get_evaluation_order, GraphError};
+use crate::parser::{ArithmeticOperator, MonkeyGraph, MonkeyType};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Pow, ToPrimitive, Zero};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvaluationMode {
+    // Exact rationals all the way through, nothing is truncated and nothing can overflow
+    #[default]
+    Exact,
+    // Every monkey has to shout an integer that fits in an i64, anything else is reported
+    Strict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvaluationError {
+    Graph(GraphError),
+    DivisionByZero(String),
+    // The monkey's division leaves a remainder, with the exact result
+    InexactDivision {
+        monkey: String,
+        value: BigRational,
+    },
+    // The monkey's result doesn't fit in an i64
+    Overflow {
+        monkey: String,
+        value: BigRational,
+    },
+    UnsupportedExponent {
+        monkey: String,
+        exponent: BigRational,
+    },
+}
+
+// Largest exponent a monkey can raise to, anything bigger is treated as a mistake in the input
+pub const MAX_EXPONENT: u32 = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperatorError {
+    DivisionByZero,
+    // Not an integer or bigger than MAX_EXPONENT
+    UnsupportedExponent(BigRational),
+}
+
+impl OperatorError {
+    pub fn for_monkey(self, monkey_name: &str) -> EvaluationError {
+        match self {
+            OperatorError::DivisionByZero => {
+                EvaluationError::DivisionByZero(monkey_name.to_string())
+            }
+            OperatorError::UnsupportedExponent(exponent) => EvaluationError::UnsupportedExponent {
+                monkey: monkey_name.to_string(),
+                exponent,
+            },
+        }
+    }
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluationError::Graph(err) => write!(f, "{}", err),
+            EvaluationError::DivisionByZero(name) => write!(f, "Monkey {} divides by zero.", name),
+            EvaluationError::InexactDivision { monkey, value } => write!(
+                f,
+                "Monkey {} divides with a remainder, the exact result is {}.",
+                monkey, value
+            ),
+            EvaluationError::Overflow { monkey, value } => {
+                write!(f, "Monkey {} overflows an i64 with {}.", monkey, value)
+            }
+            EvaluationError::UnsupportedExponent { monkey, exponent } => write!(
+                f,
+                "Monkey {} raises to {}, exponents have to be integers up to {}.",
+                monkey, exponent, MAX_EXPONENT
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EvaluationError {}
+
+impl From<GraphError> for EvaluationError {
+    fn from(err: GraphError) -> Self {
+        EvaluationError::Graph(err)
+    }
+}
+
+pub fn apply_operator(
+    operator: ArithmeticOperator,
+    left: &BigRational,
+    right: &BigRational,
+) -> Result<BigRational, OperatorError> {
+    let check_divisor = || {
+        if right.is_zero() {
+            Err(OperatorError::DivisionByZero)
+        } else {
+            Ok(())
+        }
+    };
+
+    match operator {
+        ArithmeticOperator::Add => Ok(left + right),
+        ArithmeticOperator::Subtract => Ok(left - right),
+        ArithmeticOperator::Multiply => Ok(left * right),
+        ArithmeticOperator::Divide => check_divisor().map(|_| left / right),
+        ArithmeticOperator::Modulo => {
+            check_divisor().map(|_| left - right * (left / right).floor())
+        }
+        ArithmeticOperator::Power => {
+            let exponent = get_exponent(right)?;
+            if left.is_zero() && exponent < 0 {
+                return Err(OperatorError::DivisionByZero);
+            }
+            Ok(Pow::pow(left, exponent))
+        }
+        ArithmeticOperator::Min => Ok(std::cmp::min(left, right).clone()),
+        ArithmeticOperator::Max => Ok(std::cmp::max(left, right).clone()),
+    }
+}
+
+pub fn get_exponent(exponent: &BigRational) -> Result<i32, OperatorError> {
+    exponent
+        .is_integer()
+        .then(|| exponent.to_integer().to_i32())
+        .flatten()
+        .filter(|e| e.unsigned_abs() <= MAX_EXPONENT)
+        .ok_or_else(|| OperatorError::UnsupportedExponent(exponent.clone()))
+}
+
+// What the monkey shouts, worked out exactly. Every monkey is evaluated once, after the
+// monkeys it waits on.
+pub fn evaluate(
+    monkeys: &MonkeyGraph,
+    monkey_name: &str,
+    mode: EvaluationMode,
+) -> Result<BigRational, EvaluationError> {
+    let start = monkeys.get_id(monkey_name)?;
+    let mut values: Vec<Option<BigRational>> = vec![None; monkeys.len()];
+
+    for id in get_evaluation_order(monkeys, start, &[])? {
+        let value = match monkeys.get_monkey_type(id) {
+            MonkeyType::Number(n) => BigRational::from_integer(BigInt::from(*n)),
+            MonkeyType::Other {
+                operator,
+                left,
+                right,
+            } => apply_operator(
+                *operator,
+                values[*left].as_ref().unwrap(),
+                values[*right].as_ref().unwrap(),
+            )
+            .map_err(|err| err.for_monkey(monkeys.get_name(id)))?,
+            MonkeyType::Negate(operand) => -values[*operand].as_ref().unwrap(),
+        };
+        values[id] = Some(check_value(monkeys.get_name(id), value, mode)?);
+    }
+
+    Ok(values[start].take().unwrap())
+}
+
+fn check_value(
+    monkey_name: &str,
+    value: BigRational,
+    mode: EvaluationMode,
+) -> Result<BigRational, EvaluationError> {
+    if mode == EvaluationMode::Exact {
+        return Ok(value);
+    }
+
+    if !value.is_integer() {
+        return Err(EvaluationError::InexactDivision {
+            monkey: monkey_name.to_string(),
+            value,
+        });
+    }
+
+    if value.to_integer().to_i64().is_none() {
+        return Err(EvaluationError::Overflow {
+            monkey: monkey_name.to_string(),
+            value,
+        });
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_monkeys;
+
+    fn rational(numerator: i64, denominator: i64) -> BigRational {
+        BigRational::new(BigInt::from(numerator), BigInt::from(denominator))
+    }
+
+    #[test]
+    fn strict_mode_names_the_monkey_that_divides_with_a_remainder() {
+        let monkeys =
+            parse_monkeys("root: half * two\nhalf: seven / two\nseven: 7\ntwo: 2").unwrap();
+
+        assert_eq!(
+            evaluate(&monkeys, "root", EvaluationMode::Exact),
+            Ok(rational(7, 1))
+        );
+        let err = evaluate(&monkeys, "root", EvaluationMode::Strict).unwrap_err();
+        assert_eq!(
+            err,
+            EvaluationError::InexactDivision {
+                monkey: "half".to_string(),
+                value: rational(7, 2),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Monkey half divides with a remainder, the exact result is 7/2."
+        );
+    }
+
+    #[test]
+    fn strict_mode_names_the_monkey_that_overflows() {
+        let monkeys =
+            parse_monkeys("root: big / big\nbig: ten ^ twenty\nten: 10\ntwenty: 20").unwrap();
+        let big = BigRational::from_integer(BigInt::from(10).pow(20u32));
+
+        assert_eq!(
+            evaluate(&monkeys, "root", EvaluationMode::Exact),
+            Ok(rational(1, 1))
+        );
+        let err = evaluate(&monkeys, "root", EvaluationMode::Strict).unwrap_err();
+        assert_eq!(
+            err,
+            EvaluationError::Overflow {
+                monkey: "big".to_string(),
+                value: big,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Monkey big overflows an i64 with 100000000000000000000."
+        );
+    }
+
+    #[test]
+    fn strict_mode_accepts_the_largest_i64() {
+        let monkeys =
+            parse_monkeys(&format!("root: max + zero\nmax: {}\nzero: 0", i64::MAX)).unwrap();
+        assert_eq!(
+            evaluate(&monkeys, "root", EvaluationMode::Strict),
+            Ok(BigRational::from_integer(BigInt::from(i64::MAX)))
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use crate::evaluation::apply_operator;
+use crate::expression::ExpressionError;
+use crate::graph::{get_dependencies, get_evaluation_order, GraphError};
+use crate::parser::{
+    ArithmeticOperator, MonkeyGraph, MonkeyId, MonkeyName, MonkeyType, NEGATION_PRECEDENCE,
+};
+use crate::query::Query;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Signed;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Infix,
+    SExpression,
+    Dot,
+}
+
+// What a monkey shouts as a formula, every part that doesn't depend on the unknown is folded
+// into a single constant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpressionTree {
+    Constant(BigRational),
+    Unknown(MonkeyName),
+    Operation {
+        operator: ArithmeticOperator,
+        left: Box<ExpressionTree>,
+        right: Box<ExpressionTree>,
+    },
+    Negate(Box<ExpressionTree>),
+}
+
+impl ExpressionTree {
+    pub fn build(
+        monkeys: &MonkeyGraph,
+        monkey: MonkeyId,
+        unknowns: &[MonkeyId],
+    ) -> Result<ExpressionTree, ExpressionError> {
+        let mut trees: Vec<Option<ExpressionTree>> = vec![None; monkeys.len()];
+
+        for id in get_evaluation_order(monkeys, monkey, unknowns)? {
+            let tree = match monkeys.get_monkey_type(id) {
+                _ if unknowns.contains(&id) => {
+                    ExpressionTree::Unknown(monkeys.get_name(id).to_string())
+                }
+                MonkeyType::Number(n) => {
+                    ExpressionTree::Constant(BigRational::from_integer(BigInt::from(*n)))
+                }
+                MonkeyType::Other {
+                    operator,
+                    left,
+                    right,
+                } => {
+                    let left = trees[*left].clone().unwrap();
+                    let right = trees[*right].clone().unwrap();
+                    match (&left, &right) {
+                        (ExpressionTree::Constant(l), ExpressionTree::Constant(r)) => {
+                            ExpressionTree::Constant(apply_operator(*operator, l, r).map_err(
+                                |err| {
+                                    ExpressionError::from_operator_error(err, monkeys.get_name(id))
+                                },
+                            )?)
+                        }
+                        _ => ExpressionTree::Operation {
+                            operator: *operator,
+                            left: Box::new(left),
+                            right: Box::new(right),
+                        },
+                    }
+                }
+                MonkeyType::Negate(operand) => match trees[*operand].clone().unwrap() {
+                    ExpressionTree::Constant(value) => ExpressionTree::Constant(-value),
+                    operand => ExpressionTree::Negate(Box::new(operand)),
+                },
+            };
+            trees[id] = Some(tree);
+        }
+
+        Ok(trees[monkey].take().unwrap())
+    }
+
+    // (+ 4 (* 2 (- humn 3))), negation is (- humn)
+    pub fn to_s_expression(&self) -> String {
+        match self {
+            ExpressionTree::Constant(value) => value.to_string(),
+            ExpressionTree::Unknown(name) => name.to_string(),
+            ExpressionTree::Operation {
+                operator,
+                left,
+                right,
+            } => format!(
+                "({} {} {})",
+                operator,
+                left.to_s_expression(),
+                right.to_s_expression()
+            ),
+            ExpressionTree::Negate(operand) => format!("(- {})", operand.to_s_expression()),
+        }
+    }
+
+    // Operands only get parentheses when they'd be read differently without them
+    fn fmt_operand(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        parent: ArithmeticOperator,
+        is_right: bool,
+    ) -> fmt::Result {
+        let needs_parentheses = match self {
+            ExpressionTree::Constant(value) => value.is_negative() || !value.is_integer(),
+            ExpressionTree::Unknown(_) => false,
+            ExpressionTree::Operation { operator, .. } => {
+                operator.get_precedence() < parent.get_precedence()
+                    || (operator.get_precedence() == parent.get_precedence()
+                        && is_right != parent.is_right_associative()
+                        && !(parent.is_associative() && operator == &parent))
+            }
+            // a - (-b) and (-b) ^ 2
+            ExpressionTree::Negate(_) => is_right || parent.get_precedence() >= NEGATION_PRECEDENCE,
+        };
+
+        if needs_parentheses {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+// 4 + 2 * (humn - 3)
+impl fmt::Display for ExpressionTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionTree::Constant(value) => write!(f, "{}", value),
+            ExpressionTree::Unknown(name) => write!(f, "{}", name),
+            ExpressionTree::Operation {
+                operator,
+                left,
+                right,
+            } if operator.is_function() => write!(f, "{}({}, {})", operator, left, right),
+            ExpressionTree::Operation {
+                operator,
+                left,
+                right,
+            } => {
+                left.fmt_operand(f, *operator, false)?;
+                write!(f, " {} ", operator)?;
+                right.fmt_operand(f, *operator, true)
+            }
+            ExpressionTree::Negate(operand) => {
+                let needs_parentheses = match operand.as_ref() {
+                    ExpressionTree::Constant(value) => value.is_negative() || !value.is_integer(),
+                    ExpressionTree::Unknown(_) => false,
+                    ExpressionTree::Operation { operator, .. } => {
+                        operator.get_precedence() < NEGATION_PRECEDENCE
+                    }
+                    ExpressionTree::Negate(_) => true,
+                };
+                if needs_parentheses {
+                    write!(f, "-({})", operand)
+                } else {
+                    write!(f, "-{}", operand)
+                }
+            }
+        }
+    }
+}
+
+// Graphviz graph of every monkey `monkey` waits on, with the monkeys and edges that lead to the
+// unknowns in red
+pub fn to_dot(
+    monkeys: &MonkeyGraph,
+    monkey: MonkeyId,
+    unknowns: &[MonkeyId],
+) -> Result<String, GraphError> {
+    let order = get_evaluation_order(monkeys, monkey, &[])?;
+
+    let mut has_unknown = vec![false; monkeys.len()];
+    for id in order.iter() {
+        has_unknown[*id] = unknowns.contains(id)
+            || get_dependencies(monkeys.get_monkey_type(*id))
+                .iter()
+                .any(|dependency| has_unknown[*dependency]);
+    }
+
+    let color = |id: MonkeyId| if has_unknown[id] { "red" } else { "black" };
+
+    let mut dot = vec![
+        "digraph monkeys {".to_string(),
+        "    node [shape=box];".to_string(),
+    ];
+
+    for id in order.iter().rev() {
+        let name = monkeys.get_name(*id);
+        match monkeys.get_monkey_type(*id) {
+            MonkeyType::Number(n) => dot.push(format!(
+                "    \"{}\" [label=\"{}\\n{}\", color={}];",
+                name,
+                name,
+                n,
+                color(*id)
+            )),
+            MonkeyType::Other {
+                operator,
+                left,
+                right,
+            } => {
+                dot.push(format!(
+                    "    \"{}\" [label=\"{}\\n{}\", color={}];",
+                    name,
+                    name,
+                    operator,
+                    color(*id)
+                ));
+                for (side, child) in [("left", left), ("right", right)] {
+                    let edge_color = if has_unknown[*id] && has_unknown[*child] {
+                        "red"
+                    } else {
+                        "black"
+                    };
+                    dot.push(format!(
+                        "    \"{}\" -> \"{}\" [label={}, color={}];",
+                        name,
+                        monkeys.get_name(*child),
+                        side,
+                        edge_color
+                    ));
+                }
+            }
+            MonkeyType::Negate(operand) => {
+                dot.push(format!(
+                    "    \"{}\" [label=\"{}\\n-\", color={}];",
+                    name,
+                    name,
+                    color(*id)
+                ));
+                let edge_color = if has_unknown[*id] && has_unknown[*operand] {
+                    "red"
+                } else {
+                    "black"
+                };
+                dot.push(format!(
+                    "    \"{}\" -> \"{}\" [color={}];",
+                    name,
+                    monkeys.get_name(*operand),
+                    edge_color
+                ));
+            }
+        }
+    }
+
+    dot.push("}".to_string());
+    Ok(dot.join("\n"))
+}
+
+// The query's comparison with both sides written out in the unknown
+pub fn export_query(
+    monkeys: &MonkeyGraph,
+    query: &Query,
+    format: ExportFormat,
+) -> Result<String, ExpressionError> {
+    let (target, left, right, unknowns) = query.get_ids(monkeys)?;
+
+    if format == ExportFormat::Dot {
+        return Ok(to_dot(monkeys, target, &unknowns)?);
+    }
+
+    let left = ExpressionTree::build(monkeys, left, &unknowns)?;
+    let right = ExpressionTree::build(monkeys, right, &unknowns)?;
+
+    Ok(match format {
+        ExportFormat::SExpression => format!(
+            "({} {} {})",
+            query.relation,
+            left.to_s_expression(),
+            right.to_s_expression()
+        ),
+        _ => format!("{} {} {}", left, query.relation, right),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_monkeys;
+
+    const SAMPLE: &str = include_str!("../input/sample-input.txt");
+
+    fn unknown(name: &str) -> ExpressionTree {
+        ExpressionTree::Unknown(name.to_string())
+    }
+
+    fn constant(numerator: i64, denominator: i64) -> ExpressionTree {
+        ExpressionTree::Constant(BigRational::new(
+            BigInt::from(numerator),
+            BigInt::from(denominator),
+        ))
+    }
+
+    fn operation(
+        left: ExpressionTree,
+        operator: ArithmeticOperator,
+        right: ExpressionTree,
+    ) -> ExpressionTree {
+        ExpressionTree::Operation {
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn negate(operand: ExpressionTree) -> ExpressionTree {
+        ExpressionTree::Negate(Box::new(operand))
+    }
+
+    #[test]
+    fn sample_query_is_exported() {
+        let monkeys = parse_monkeys(SAMPLE).unwrap();
+        let query = Query::default();
+
+        assert_eq!(
+            export_query(&monkeys, &query, ExportFormat::Infix).unwrap(),
+            "(4 + 2 * (humn - 3)) / 4 = 150"
+        );
+        assert_eq!(
+            export_query(&monkeys, &query, ExportFormat::SExpression).unwrap(),
+            "(= (/ (+ 4 (* 2 (- humn 3))) 4) 150)"
+        );
+    }
+
+    #[test]
+    fn parentheses_only_where_needed() {
+        use ArithmeticOperator::{Add, Divide, Max, Multiply, Power, Subtract};
+        let (a, b, c) = (unknown("a"), unknown("b"), unknown("c"));
+
+        for (tree, expected) in [
+            // Equal precedence on the right
+            (
+                operation(
+                    a.clone(),
+                    Subtract,
+                    operation(b.clone(), Subtract, c.clone()),
+                ),
+                "a - (b - c)",
+            ),
+            (
+                operation(
+                    operation(a.clone(), Subtract, b.clone()),
+                    Subtract,
+                    c.clone(),
+                ),
+                "a - b - c",
+            ),
+            (
+                operation(a.clone(), Add, operation(b.clone(), Add, c.clone())),
+                "a + b + c",
+            ),
+            (
+                operation(a.clone(), Add, operation(b.clone(), Subtract, c.clone())),
+                "a + (b - c)",
+            ),
+            (
+                operation(a.clone(), Multiply, operation(b.clone(), Divide, c.clone())),
+                "a * (b / c)",
+            ),
+            (
+                operation(operation(a.clone(), Add, b.clone()), Multiply, c.clone()),
+                "(a + b) * c",
+            ),
+            // Power is right associative
+            (
+                operation(operation(a.clone(), Power, b.clone()), Power, c.clone()),
+                "(a ^ b) ^ c",
+            ),
+            (
+                operation(a.clone(), Power, operation(b.clone(), Power, c.clone())),
+                "a ^ b ^ c",
+            ),
+            // Negation binds looser than power and tighter than multiplication
+            (
+                operation(negate(unknown("x")), Power, constant(2, 1)),
+                "(-x) ^ 2",
+            ),
+            (
+                negate(operation(unknown("x"), Power, constant(2, 1))),
+                "-x ^ 2",
+            ),
+            (
+                negate(operation(a.clone(), Multiply, b.clone())),
+                "-(a * b)",
+            ),
+            (operation(negate(a.clone()), Multiply, b.clone()), "-a * b"),
+            (
+                operation(a.clone(), Subtract, negate(b.clone())),
+                "a - (-b)",
+            ),
+            (operation(negate(a.clone()), Subtract, b.clone()), "-a - b"),
+            (negate(negate(a.clone())), "-(-a)"),
+            // Negative and fractional constants
+            (operation(a.clone(), Multiply, constant(-3, 1)), "a * (-3)"),
+            (operation(a.clone(), Add, constant(1, 2)), "a + (1/2)"),
+            (negate(constant(-3, 1)), "-(-3)"),
+            // Functions keep their arguments as they are
+            (
+                operation(
+                    operation(a.clone(), Subtract, b.clone()),
+                    Max,
+                    negate(c.clone()),
+                ),
+                "max(a - b, -c)",
+            ),
+        ] {
+            assert_eq!(tree.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn negation_in_s_expressions() {
+        let tree = operation(
+            negate(unknown("humn")),
+            ArithmeticOperator::Min,
+            constant(1, 2),
+        );
+        assert_eq!(tree.to_s_expression(), "(min (- humn) 1/2)");
+    }
+
+    #[test]
+    fn dot_marks_the_path_to_the_unknown() {
+        let monkeys = parse_monkeys("root: a + b\na: humn - c\nb: -c\nc: 3\nhumn: 5").unwrap();
+
+        assert_eq!(
+            export_query(&monkeys, &Query::default(), ExportFormat::Dot).unwrap(),
+            r#"digraph monkeys {
+    node [shape=box];
+    "root" [label="root\n+", color=red];
+    "root" -> "a" [label=left, color=red];
+    "root" -> "b" [label=right, color=black];
+    "b" [label="b\n-", color=black];
+    "b" -> "c" [color=black];
+    "a" [label="a\n-", color=red];
+    "a" -> "humn" [label=left, color=red];
+    "a" -> "c" [label=right, color=black];
+    "c" [label="c\n3", color=black];
+    "humn" [label="humn\n5", color=red];
+}"#
+        );
+    }
+}
@@ -0,0 +1,555 @@
+use crate::evaluation::{apply_operator, get_exponent, OperatorError, MAX_EXPONENT};
+use crate::graph::GraphError;
+use crate::inequality::IntegerRange;
+use crate::parser::ArithmeticOperator;
+use crate::piecewise::{IntegerClass, MAX_CASES};
+use crate::query::Relation;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+
+// Integer roots of higher degree polynomials are found by trying divisors of the constant term,
+// the search gives up instead of trying more than this many
+const MAX_ROOT_CANDIDATES: u64 = 10_000_000;
+
+// Inequalities go through every derivative of the polynomial, so the work grows with the square
+// of the degree. Anything above this is given up on instead of running for minutes.
+const MAX_INEQUALITY_DEGREE: usize = 128;
+
+// Coefficients of a polynomial in the unknown, the i-th one belongs to x^i. Trailing zeros are
+// always trimmed, so the zero polynomial has no coefficients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial(pub Vec<BigRational>);
+
+// Numerator / denominator. Every division by a constant is folded into the numerator, so the
+// denominator only has the unknown in it when something was divided by the unknown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    pub numerator: Polynomial,
+    pub denominator: Polynomial,
+    // Divisors that have the unknown in them, the expression is undefined wherever one is zero.
+    // Dividing by a fraction moves its denominator up, so this can't be read off the denominator.
+    pub undefined_at: Vec<Polynomial>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solution {
+    // Every integer that makes both sides equal, in increasing order
+    Values(Vec<BigInt>),
+    // Both sides are equal whatever the unknown is, apart from values that divide by zero
+    InfinitelyMany,
+    // No integer makes both sides equal, with the exact solutions that aren't integers if any
+    // were found
+    NoIntegerSolution(Vec<BigRational>),
+    // Every integer that satisfies an inequality, empty when none do
+    Ranges(Vec<IntegerRange>),
+    // Every integer in any of the classes, when modulo makes the solutions skip integers
+    Classes(Vec<IntegerClass>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpressionError {
+    Graph(GraphError),
+    // The monkey divides by something that is always zero
+    DivisionByZero(String),
+    UnsupportedExponent {
+        monkey: String,
+        exponent: BigRational,
+    },
+    // The unknown shows up somewhere the result can't be split into fractions of polynomials
+    // in it, like in an exponent, in the divisor of modulo or in modulo of a division by it
+    NotPolynomial {
+        monkey: String,
+        operator: ArithmeticOperator,
+    },
+    // Too many candidates to look for integer roots of a polynomial of this degree
+    UnsupportedEquation {
+        degree: usize,
+    },
+    // The monkey shouts a number, so there are no two sides to compare
+    NothingToCompare(String),
+    // The monkey's min, max or modulo splits the unknown into more than MAX_CASES cases
+    TooManyCases(String),
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionError::Graph(err) => write!(f, "{}", err),
+            ExpressionError::DivisionByZero(name) => {
+                write!(f, "Monkey {} divides by zero.", name)
+            }
+            ExpressionError::UnsupportedExponent { monkey, exponent } => write!(
+                f,
+                "Monkey {} raises to {}, exponents have to be integers up to {}.",
+                monkey, exponent, MAX_EXPONENT
+            ),
+            ExpressionError::NotPolynomial { monkey, operator } => write!(
+                f,
+                "Monkey {} can't be solved for, the unknown is used with {}.",
+                monkey, operator
+            ),
+            ExpressionError::UnsupportedEquation { degree } => write!(
+                f,
+                "Can't search for integer solutions of a degree {} equation.",
+                degree
+            ),
+            ExpressionError::NothingToCompare(name) => {
+                write!(f, "Monkey {} has no two sides to compare.", name)
+            }
+            ExpressionError::TooManyCases(name) => write!(
+                f,
+                "Monkey {} splits the unknown into more than {} cases.",
+                name, MAX_CASES
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+impl ExpressionError {
+    pub fn from_operator_error(err: OperatorError, monkey_name: &str) -> ExpressionError {
+        match err {
+            OperatorError::DivisionByZero => {
+                ExpressionError::DivisionByZero(monkey_name.to_string())
+            }
+            OperatorError::UnsupportedExponent(exponent) => ExpressionError::UnsupportedExponent {
+                monkey: monkey_name.to_string(),
+                exponent,
+            },
+        }
+    }
+}
+
+impl From<GraphError> for ExpressionError {
+    fn from(err: GraphError) -> Self {
+        ExpressionError::Graph(err)
+    }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Solution::Values(values) => write!(
+                f,
+                "{}",
+                values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" or ")
+            ),
+            Solution::InfinitelyMany => write!(f, "Infinitely many solutions"),
+            Solution::NoIntegerSolution(roots) if roots.is_empty() => {
+                write!(f, "No integer solution")
+            }
+            Solution::NoIntegerSolution(roots) => write!(
+                f,
+                "No integer solution, the exact solution is {}",
+                roots
+                    .iter()
+                    .map(|root| root.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" or ")
+            ),
+            Solution::Ranges(ranges) if ranges.is_empty() => write!(f, "No integer solution"),
+            Solution::Ranges(ranges) => write!(
+                f,
+                "{}",
+                ranges
+                    .iter()
+                    .map(|range| range.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" or ")
+            ),
+            Solution::Classes(classes) if classes.is_empty() => write!(f, "No integer solution"),
+            Solution::Classes(classes) => write!(
+                f,
+                "{}",
+                classes
+                    .iter()
+                    .map(|class| class.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" or ")
+            ),
+        }
+    }
+}
+
+impl Polynomial {
+    pub fn zero() -> Polynomial {
+        Polynomial(vec![])
+    }
+
+    pub fn constant(value: BigRational) -> Polynomial {
+        Polynomial(vec![value]).trim()
+    }
+
+    pub fn unknown() -> Polynomial {
+        Polynomial(vec![BigRational::zero(), BigRational::one()])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // None for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.0.len().checked_sub(1)
+    }
+
+    pub fn as_constant(&self) -> Option<BigRational> {
+        match self.0.len() {
+            0 => Some(BigRational::zero()),
+            1 => Some(self.0[0].clone()),
+            _ => None,
+        }
+    }
+
+    pub fn evaluate(&self, x: &BigRational) -> BigRational {
+        self.0
+            .iter()
+            .rev()
+            .fold(BigRational::zero(), |acc, coefficient| {
+                acc * x + coefficient
+            })
+    }
+
+    pub fn add(&self, other: &Polynomial) -> Polynomial {
+        let len = self.0.len().max(other.0.len());
+        Polynomial(
+            (0..len)
+                .map(|i| self.get(i) + other.get(i))
+                .collect::<Vec<BigRational>>(),
+        )
+        .trim()
+    }
+
+    pub fn subtract(&self, other: &Polynomial) -> Polynomial {
+        self.add(&other.scale(&-BigRational::one()))
+    }
+
+    pub fn multiply(&self, other: &Polynomial) -> Polynomial {
+        if self.is_zero() || other.is_zero() {
+            return Polynomial::zero();
+        }
+
+        let mut coefficients = vec![BigRational::zero(); self.0.len() + other.0.len() - 1];
+        for (i, a) in self.0.iter().enumerate() {
+            for (j, b) in other.0.iter().enumerate() {
+                coefficients[i + j] += a * b;
+            }
+        }
+        Polynomial(coefficients).trim()
+    }
+
+    pub fn scale(&self, factor: &BigRational) -> Polynomial {
+        Polynomial(self.0.iter().map(|c| c * factor).collect()).trim()
+    }
+
+    fn get(&self, i: usize) -> BigRational {
+        self.0.get(i).cloned().unwrap_or_else(BigRational::zero)
+    }
+
+    fn trim(mut self) -> Polynomial {
+        while self.0.last().is_some_and(|c| c.is_zero()) {
+            self.0.pop();
+        }
+        self
+    }
+
+    // Same roots with integer coefficients, found by multiplying by every denominator
+    fn to_integer_coefficients(&self) -> Vec<BigInt> {
+        let lcm = self
+            .0
+            .iter()
+            .fold(BigInt::one(), |lcm, c| lcm.lcm(c.denom()));
+        self.0
+            .iter()
+            .map(|c| (c * BigRational::from_integer(lcm.clone())).to_integer())
+            .collect()
+    }
+
+    // Roots of a non zero polynomial. Integer roots are always all found, roots that aren't
+    // integers are only found for linear polynomials.
+    fn get_roots(&self) -> Result<Vec<BigRational>, ExpressionError> {
+        match self.degree() {
+            None | Some(0) => Ok(vec![]),
+            Some(1) => Ok(vec![-self.0[0].clone() / self.0[1].clone()]),
+            Some(degree) => {
+                let coefficients = self.to_integer_coefficients();
+
+                // x^k factors out of the polynomial, so 0 is a root and the rest come from what's left
+                let zeros = coefficients.iter().take_while(|c| c.is_zero()).count();
+                let coefficients = &coefficients[zeros..];
+                let mut roots: Vec<BigInt> = if zeros > 0 {
+                    vec![BigInt::zero()]
+                } else {
+                    vec![]
+                };
+
+                let constant = coefficients[0].abs();
+                let leading = coefficients[coefficients.len() - 1].abs();
+
+                // Every root is smaller than the Cauchy bound and every integer root divides the
+                // constant term
+                let bound = BigInt::one()
+                    + coefficients
+                        .iter()
+                        .map(|c| c.abs())
+                        .max()
+                        .unwrap_or_else(BigInt::zero)
+                        .div_ceil(&leading);
+                let limit = std::cmp::min(bound.clone(), constant.sqrt());
+
+                if limit > BigInt::from(MAX_ROOT_CANDIDATES) {
+                    return Err(ExpressionError::UnsupportedEquation { degree });
+                }
+
+                let is_root = |x: &BigInt| {
+                    coefficients
+                        .iter()
+                        .rev()
+                        .fold(BigInt::zero(), |acc, c| acc * x + c)
+                        .is_zero()
+                };
+
+                for i in 1..=limit.to_u64().unwrap_or(0) {
+                    let divisor = BigInt::from(i);
+                    if !constant.is_multiple_of(&divisor) {
+                        continue;
+                    }
+                    for candidate in [divisor.clone(), &constant / &divisor] {
+                        if candidate > bound {
+                            continue;
+                        }
+                        for candidate in [candidate.clone(), -candidate] {
+                            if !roots.contains(&candidate) && is_root(&candidate) {
+                                roots.push(candidate);
+                            }
+                        }
+                    }
+                }
+
+                roots.sort();
+                Ok(roots.into_iter().map(BigRational::from_integer).collect())
+            }
+        }
+    }
+}
+
+impl Expression {
+    pub fn constant(value: BigRational) -> Expression {
+        Expression {
+            numerator: Polynomial::constant(value),
+            denominator: Polynomial::constant(BigRational::one()),
+            undefined_at: vec![],
+        }
+    }
+
+    pub fn unknown() -> Expression {
+        Expression {
+            numerator: Polynomial::unknown(),
+            denominator: Polynomial::constant(BigRational::one()),
+            undefined_at: vec![],
+        }
+    }
+
+    // Some when the unknown doesn't show up in the expression
+    pub fn as_constant(&self) -> Option<BigRational> {
+        Some(self.numerator.as_constant()? / self.denominator.as_constant()?)
+    }
+
+    pub fn negate(&self) -> Expression {
+        Expression {
+            numerator: self.numerator.scale(&-BigRational::one()),
+            denominator: self.denominator.clone(),
+            undefined_at: self.undefined_at.clone(),
+        }
+    }
+
+    // Errors name `monkey_name` as the monkey applying the operator
+    pub fn apply(
+        &self,
+        operator: ArithmeticOperator,
+        other: &Expression,
+        monkey_name: &str,
+    ) -> Result<Expression, ExpressionError> {
+        let (a, b) = (&self.numerator, &self.denominator);
+        let (c, d) = (&other.numerator, &other.denominator);
+
+        let mut undefined_at = self.undefined_at.clone();
+        for divisor in other.undefined_at.iter() {
+            if !undefined_at.contains(divisor) {
+                undefined_at.push(divisor.clone());
+            }
+        }
+        if operator == ArithmeticOperator::Divide
+            && c.as_constant().is_none()
+            && !undefined_at.contains(c)
+        {
+            undefined_at.push(c.clone());
+        }
+
+        let (numerator, denominator) = match operator {
+            ArithmeticOperator::Add => (a.multiply(d).add(&c.multiply(b)), b.multiply(d)),
+            ArithmeticOperator::Subtract => (a.multiply(d).subtract(&c.multiply(b)), b.multiply(d)),
+            ArithmeticOperator::Multiply => (a.multiply(c), b.multiply(d)),
+            ArithmeticOperator::Divide => {
+                if c.is_zero() {
+                    return Err(ExpressionError::DivisionByZero(monkey_name.to_string()));
+                }
+                (a.multiply(d), b.multiply(c))
+            }
+            ArithmeticOperator::Modulo
+            | ArithmeticOperator::Power
+            | ArithmeticOperator::Min
+            | ArithmeticOperator::Max => {
+                let not_polynomial = || ExpressionError::NotPolynomial {
+                    monkey: monkey_name.to_string(),
+                    operator,
+                };
+                let exponent = other.as_constant().ok_or_else(not_polynomial)?;
+
+                let Some(base) = self.as_constant() else {
+                    if operator != ArithmeticOperator::Power {
+                        return Err(not_polynomial());
+                    }
+                    return self.pow(&exponent, monkey_name);
+                };
+
+                let value = apply_operator(operator, &base, &exponent)
+                    .map_err(|err| ExpressionError::from_operator_error(err, monkey_name))?;
+                (
+                    Polynomial::constant(value),
+                    Polynomial::constant(BigRational::one()),
+                )
+            }
+        };
+
+        Ok(Expression::normalize(numerator, denominator, undefined_at))
+    }
+
+    // Exponentiation by squaring, a negative exponent divides 1 by the result
+    fn pow(
+        &self,
+        exponent: &BigRational,
+        monkey_name: &str,
+    ) -> Result<Expression, ExpressionError> {
+        let exponent = get_exponent(exponent)
+            .map_err(|err| ExpressionError::from_operator_error(err, monkey_name))?;
+
+        let mut result = Expression::constant(BigRational::one());
+        let mut square = self.clone();
+        let mut remaining = exponent.unsigned_abs();
+        while remaining > 0 {
+            if remaining % 2 == 1 {
+                result = result.apply(ArithmeticOperator::Multiply, &square, monkey_name)?;
+            }
+            remaining /= 2;
+            if remaining > 0 {
+                square = square.apply(ArithmeticOperator::Multiply, &square, monkey_name)?;
+            }
+        }
+
+        if exponent < 0 {
+            result = Expression::constant(BigRational::one()).apply(
+                ArithmeticOperator::Divide,
+                &result,
+                monkey_name,
+            )?;
+        }
+
+        Ok(result)
+    }
+
+    // Divides both sides by the leading coefficient of the denominator, so a constant
+    // denominator is always 1
+    fn normalize(
+        numerator: Polynomial,
+        denominator: Polynomial,
+        undefined_at: Vec<Polynomial>,
+    ) -> Expression {
+        let leading = BigRational::one() / denominator.0[denominator.0.len() - 1].clone();
+        Expression {
+            numerator: numerator.scale(&leading),
+            denominator: denominator.scale(&leading),
+            undefined_at,
+        }
+    }
+
+    // True when some division by the unknown is a division by zero for this value
+    pub fn is_undefined_at(&self, x: &BigRational) -> bool {
+        self.undefined_at
+            .iter()
+            .any(|divisor| divisor.evaluate(x).is_zero())
+    }
+
+    pub fn solve(
+        &self,
+        relation: Relation,
+        other: &Expression,
+    ) -> Result<Solution, ExpressionError> {
+        let sign = match relation {
+            Relation::Equal => return self.solve_equal(other),
+            Relation::LessThan => Ordering::Less,
+            Relation::GreaterThan => Ordering::Greater,
+        };
+
+        // a / b - c / d = (a * d - c * b) / (b * d) has the same sign as
+        // (a * d - c * b) * (b * d). Multiplying by the square of every divisor keeps the sign
+        // and makes it zero wherever either side is undefined, so those values never match.
+        let difference = self
+            .undefined_at
+            .iter()
+            .chain(other.undefined_at.iter())
+            .fold(
+                self.numerator
+                    .multiply(&other.denominator)
+                    .subtract(&other.numerator.multiply(&self.denominator))
+                    .multiply(&self.denominator.multiply(&other.denominator)),
+                |difference, divisor| difference.multiply(divisor).multiply(divisor),
+            );
+
+        if let Some(degree) = difference.degree().filter(|d| *d > MAX_INEQUALITY_DEGREE) {
+            return Err(ExpressionError::UnsupportedEquation { degree });
+        }
+
+        Ok(Solution::Ranges(difference.get_sign_ranges(sign)))
+    }
+
+    // Solves self = other exactly. Values that divide by zero are never solutions.
+    pub fn solve_equal(&self, other: &Expression) -> Result<Solution, ExpressionError> {
+        // a / b = c / d has the same solutions as a * d - c * b = 0, where b and d aren't zero
+        let difference = self
+            .numerator
+            .multiply(&other.denominator)
+            .subtract(&other.numerator.multiply(&self.denominator));
+
+        if difference.is_zero() {
+            return Ok(Solution::InfinitelyMany);
+        }
+
+        let roots: Vec<BigRational> = difference
+            .get_roots()?
+            .into_iter()
+            .filter(|root| !self.is_undefined_at(root) && !other.is_undefined_at(root))
+            .collect();
+
+        let values: Vec<BigInt> = roots
+            .iter()
+            .filter(|root| root.is_integer())
+            .map(|root| root.to_integer())
+            .collect();
+
+        if values.is_empty() {
+            Ok(Solution::NoIntegerSolution(roots))
+        } else {
+            Ok(Solution::Values(values))
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::parser::{MonkeyGraph, MonkeyId, MonkeyType};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    // A monkey that isn't in the input, with the monkey waiting on it
+    UnknownMonkey {
+        monkey: String,
+        referenced_by: Option<String>,
+    },
+    // Monkeys that end up waiting on themselves, the first one is repeated at the end
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::UnknownMonkey {
+                monkey,
+                referenced_by: Some(referenced_by),
+            } => write!(
+                f,
+                "Monkey with name {} not found, {} is waiting on it.",
+                monkey, referenced_by
+            ),
+            GraphError::UnknownMonkey { monkey, .. } => {
+                write!(f, "Monkey with name {} not found.", monkey)
+            }
+            GraphError::Cycle(cycle) => {
+                write!(f, "Monkeys wait on each other: {}", cycle.join(" -> "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+// Monkeys the given monkey is waiting on
+pub fn get_dependencies(monkey_type: &MonkeyType) -> Vec<MonkeyId> {
+    match monkey_type {
+        MonkeyType::Number(_) => vec![],
+        MonkeyType::Other { left, right, .. } => vec![*left, *right],
+        MonkeyType::Negate(operand) => vec![*operand],
+    }
+}
+
+// Every monkey `start` depends on, ordered so a monkey always comes after the ones it waits
+// on, ending with `start`. Monkeys in `stop_at` are included but what they wait on isn't.
+pub fn get_evaluation_order(
+    monkeys: &MonkeyGraph,
+    start: MonkeyId,
+    stop_at: &[MonkeyId],
+) -> Result<Vec<MonkeyId>, GraphError> {
+    let mut order = vec![];
+    visit(
+        monkeys,
+        start,
+        stop_at,
+        &mut vec![None; monkeys.len()],
+        &mut order,
+    )?;
+    Ok(order)
+}
+
+// Checks nothing waits on itself, monkeys that don't exist are caught when the names are
+// turned into ids
+pub fn validate_monkeys(monkeys: &MonkeyGraph) -> Result<(), GraphError> {
+    let mut visits = vec![None; monkeys.len()];
+    let mut order = vec![];
+
+    for id in 0..monkeys.len() {
+        if visits[id].is_none() {
+            visit(monkeys, id, &[], &mut visits, &mut order)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Depth first walk from `start` that adds every monkey to `order` once everything it waits on
+// has been added. Uses an explicit stack, so long chains can't overflow the call stack.
+fn visit(
+    monkeys: &MonkeyGraph,
+    start: MonkeyId,
+    stop_at: &[MonkeyId],
+    visits: &mut [Option<Visit>],
+    order: &mut Vec<MonkeyId>,
+) -> Result<(), GraphError> {
+    let get_children = |id: MonkeyId| {
+        if stop_at.contains(&id) {
+            vec![]
+        } else {
+            get_dependencies(monkeys.get_monkey_type(id))
+        }
+    };
+
+    visits[start] = Some(Visit::InProgress);
+    // Monkeys being visited and the index of the next child to look at
+    let mut stack: Vec<(MonkeyId, usize)> = vec![(start, 0)];
+
+    while let Some((id, child_index)) = stack.last().copied() {
+        let children = get_children(id);
+
+        let Some(child) = children.get(child_index).copied() else {
+            visits[id] = Some(Visit::Done);
+            order.push(id);
+            stack.pop();
+            continue;
+        };
+
+        stack.last_mut().unwrap().1 += 1;
+
+        match visits[child] {
+            Some(Visit::Done) => {}
+            Some(Visit::InProgress) => {
+                let cycle_start = stack.iter().position(|(i, _)| *i == child).unwrap();
+                let mut cycle: Vec<String> = stack[cycle_start..]
+                    .iter()
+                    .map(|(i, _)| monkeys.get_name(*i).to_string())
+                    .collect();
+                cycle.push(monkeys.get_name(child).to_string());
+                return Err(GraphError::Cycle(cycle));
+            }
+            None => {
+                visits[child] = Some(Visit::InProgress);
+                stack.push((child, 0));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::{evaluate, EvaluationMode};
+    use crate::parser::parse_monkeys;
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+
+    #[test]
+    fn cycles_are_reported_with_their_path() {
+        let err =
+            parse_monkeys("root: a + one\na: b * one\nb: c - one\nc: one + a\none: 1").unwrap_err();
+        assert_eq!(
+            err,
+            GraphError::Cycle(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "c".to_string(),
+                "a".to_string()
+            ])
+        );
+        assert_eq!(
+            err.to_string(),
+            "Monkeys wait on each other: a -> b -> c -> a"
+        );
+
+        assert_eq!(
+            parse_monkeys("root: root + one\none: 1").unwrap_err(),
+            GraphError::Cycle(vec!["root".to_string(), "root".to_string()])
+        );
+        assert_eq!(
+            parse_monkeys("root: -neg\nneg: -root").unwrap_err(),
+            GraphError::Cycle(vec![
+                "root".to_string(),
+                "neg".to_string(),
+                "root".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn unknown_monkeys_are_reported_when_parsing() {
+        let err = parse_monkeys("root: a + b\na: 1\nb: min(a, missing)").unwrap_err();
+        assert_eq!(
+            err,
+            GraphError::UnknownMonkey {
+                monkey: "missing".to_string(),
+                referenced_by: Some("b".to_string()),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Monkey with name missing not found, b is waiting on it."
+        );
+    }
+
+    #[test]
+    fn unknown_target_is_reported() {
+        let monkeys = parse_monkeys("root: 1").unwrap();
+        let err = evaluate(&monkeys, "nobody", EvaluationMode::Exact).unwrap_err();
+        assert_eq!(err.to_string(), "Monkey with name nobody not found.");
+    }
+
+    // Deep enough to overflow the stack of a test thread if the walk recursed
+    #[test]
+    fn long_chains_are_evaluated() {
+        const LENGTH: usize = 200_000;
+        let mut input: Vec<String> = (0..LENGTH)
+            .map(|i| format!("m{}: m{} + one", i, i + 1))
+            .collect();
+        input.push(format!("m{}: 0", LENGTH));
+        input.push("one: 1".to_string());
+
+        let monkeys = parse_monkeys(&input.join("\n")).unwrap();
+        assert_eq!(
+            evaluate(&monkeys, "m0", EvaluationMode::Strict),
+            Ok(BigRational::from_integer(BigInt::from(LENGTH)))
+        );
+    }
+}
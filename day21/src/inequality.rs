@@ -0,0 +1,229 @@
+use crate::expression::Polynomial;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+
+// Integers from `start` to `end`, both included. None is unbounded on that side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegerRange {
+    pub start: Option<BigInt>,
+    pub end: Option<BigInt>,
+}
+
+// Same notation as Rust ranges: 3..=7, ..=5, 10.. and .. for every integer
+impl fmt::Display for IntegerRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(start) = &self.start {
+            write!(f, "{}", start)?;
+        }
+        write!(f, "..")?;
+        if let Some(end) = &self.end {
+            write!(f, "={}", end)?;
+        }
+        Ok(())
+    }
+}
+
+// First integer where the predicate is false, the predicate has to be true up to some point
+// in [lo, hi] and false after it
+fn partition_point(lo: &BigInt, hi: &BigInt, predicate: impl Fn(&BigInt) -> bool) -> BigInt {
+    let (mut lo, mut hi) = (lo.clone(), hi + BigInt::one());
+    while lo < hi {
+        let mid = (&lo + &hi).div_floor(&BigInt::from(2));
+        if predicate(&mid) {
+            lo = mid + BigInt::one();
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+impl Polynomial {
+    pub fn derivative(&self) -> Polynomial {
+        Polynomial(
+            self.0
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| c * BigRational::from_integer(BigInt::from(i)))
+                .collect(),
+        )
+    }
+
+    pub fn get_sign(&self, x: &BigInt) -> Ordering {
+        self.evaluate(&BigRational::from_integer(x.clone()))
+            .cmp(&BigRational::zero())
+    }
+
+    // Every real root is strictly between -bound and bound (Cauchy's bound)
+    fn get_root_bound(&self) -> BigInt {
+        let Some(leading) = self.0.last() else {
+            return BigInt::one();
+        };
+        let max = self
+            .0
+            .iter()
+            .map(|c| (c / leading).abs())
+            .max()
+            .unwrap_or_else(BigRational::zero);
+        BigInt::one() + max.ceil().to_integer()
+    }
+
+    // Splits the integers into ranges separated by the breakpoints, where the polynomial is
+    // monotone over every range. Unbounded ends are cut off past the root bound, where the
+    // sign can't change any more, the returned flags say which ends were cut. `breakpoints` are
+    // the derivative's.
+    fn get_monotone_pieces(&self, breakpoints: &[BigInt]) -> Vec<(BigInt, BigInt, bool, bool)> {
+        let bound = self.get_root_bound() + BigInt::one();
+
+        let mut pieces = vec![];
+        let mut lo: Option<BigInt> = None;
+
+        for hi in breakpoints.iter().map(Some).chain([None]) {
+            let lo_value = match (&lo, hi) {
+                (Some(lo), _) => lo.clone(),
+                (None, Some(hi)) => std::cmp::min(-bound.clone(), hi.clone()),
+                (None, None) => -bound.clone(),
+            };
+            let hi_value = match hi {
+                Some(hi) => hi.clone(),
+                None => std::cmp::max(bound.clone(), lo_value.clone()),
+            };
+            if lo_value <= hi_value {
+                pieces.push((lo_value, hi_value, lo.is_none(), hi.is_none()));
+            }
+            lo = hi.map(|hi| hi + BigInt::one());
+        }
+
+        pieces
+    }
+
+    // Integers b such that no real root lies strictly between b + 1 and the next breakpoint.
+    // Found from the breakpoints of the derivative, the polynomial is monotone between those so
+    // every root in between shows up as a sign change.
+    fn get_breakpoints(&self) -> Vec<BigInt> {
+        if self.degree().is_none_or(|degree| degree == 0) {
+            return vec![];
+        }
+
+        // Worked out once, recursing twice per degree would take exponential time
+        let mut breakpoints = self.derivative().get_breakpoints();
+
+        for (lo, hi, _, _) in self.get_monotone_pieces(&breakpoints) {
+            let lo_sign = self.get_sign(&lo);
+            if lo_sign == Ordering::Equal {
+                breakpoints.push(lo);
+                continue;
+            }
+            if self.get_sign(&hi) == lo_sign {
+                continue;
+            }
+            let crossing = partition_point(&lo, &hi, |x| self.get_sign(x) == lo_sign);
+            breakpoints.push(&crossing - BigInt::one());
+            breakpoints.push(crossing);
+        }
+
+        breakpoints.sort();
+        breakpoints.dedup();
+        breakpoints
+    }
+
+    // Every integer where the polynomial has the given sign, as ranges in increasing order. Only
+    // for Less and Greater, roots are found exactly by `Expression::solve_equal` instead.
+    pub fn get_sign_ranges(&self, sign: Ordering) -> Vec<IntegerRange> {
+        let mut ranges: Vec<IntegerRange> = vec![];
+
+        let breakpoints = self.derivative().get_breakpoints();
+        for (lo, hi, unbounded_lo, unbounded_hi) in self.get_monotone_pieces(&breakpoints) {
+            let matches = |x: &BigInt| self.get_sign(x) == sign;
+
+            // Monotone, so the matching integers are at the start or at the end of the piece
+            let range = if matches(&lo) {
+                let end = partition_point(&lo, &hi, matches) - BigInt::one();
+                IntegerRange {
+                    start: (!unbounded_lo).then_some(lo),
+                    end: (!(unbounded_hi && end == hi)).then_some(end),
+                }
+            } else if matches(&hi) {
+                IntegerRange {
+                    start: Some(partition_point(&lo, &hi, |x| !matches(x))),
+                    end: (!unbounded_hi).then_some(hi),
+                }
+            } else {
+                continue;
+            };
+
+            match ranges.last_mut() {
+                Some(last)
+                    if last
+                        .end
+                        .as_ref()
+                        .zip(range.start.as_ref())
+                        .is_some_and(|(end, start)| end + BigInt::one() == *start) =>
+                {
+                    last.end = range.end
+                }
+                _ => ranges.push(range),
+            }
+        }
+
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polynomial(coefficients: &[i64]) -> Polynomial {
+        Polynomial(
+            coefficients
+                .iter()
+                .map(|c| BigRational::from_integer(BigInt::from(*c)))
+                .collect(),
+        )
+    }
+
+    fn range(start: Option<i64>, end: Option<i64>) -> IntegerRange {
+        IntegerRange {
+            start: start.map(BigInt::from),
+            end: end.map(BigInt::from),
+        }
+    }
+
+    #[test]
+    fn sign_ranges_match_brute_force() {
+        // (x + 3)(x - 1)(x - 4)^2 (x - 7) and x^3 - 2x
+        for coefficients in [vec![336, -440, 77, 39, -13, 1], vec![0, -2, 0, 1]] {
+            let p = polynomial(&coefficients);
+            for sign in [Ordering::Less, Ordering::Greater] {
+                let ranges = p.get_sign_ranges(sign);
+                for x in -50..=50 {
+                    let x = BigInt::from(x);
+                    let in_ranges = ranges.iter().any(|r| {
+                        r.start.as_ref().is_none_or(|start| start <= &x)
+                            && r.end.as_ref().is_none_or(|end| &x <= end)
+                    });
+                    assert_eq!(in_ranges, p.get_sign(&x) == sign, "{:?} at {}", sign, x);
+                }
+            }
+        }
+    }
+
+    // Used to take time exponential in the degree
+    #[test]
+    fn high_degree_is_fast() {
+        let mut coefficients = vec![0; 61];
+        coefficients[0] = -1000;
+        coefficients[60] = 1;
+
+        assert_eq!(
+            polynomial(&coefficients).get_sign_ranges(Ordering::Less),
+            vec![range(Some(-1), Some(1))]
+        );
+    }
+}
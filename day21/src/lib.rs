@@ -1,5 +1,22 @@
+mod evaluation;
+mod export;
+mod expression;
+mod graph;
+mod inequality;
 mod parser;
 mod part1;
 mod part2;
+mod piecewise;
+mod query;
 
-pub use parser::Day21 as Problem;
+pub use evaluation::{apply_operator, evaluate, EvaluationError, EvaluationMode};
+pub use export::{export_query, to_dot, ExportFormat, ExpressionTree};
+pub use expression::{Expression, ExpressionError, Polynomial, Solution};
+pub use graph::{get_dependencies, get_evaluation_order, validate_monkeys, GraphError};
+pub use inequality::IntegerRange;
+pub use parser::{
+    parse_monkeys, ArithmeticOperator, Day21 as Problem, MonkeyGraph, MonkeyId, MonkeyName,
+    MonkeyType,
+};
+pub use piecewise::{build_piecewise, IntegerClass, Piecewise, MAX_CASES};
+pub use query::{Query, Relation};
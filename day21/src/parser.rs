@@ -1,34 +1,99 @@
+use crate::evaluation::EvaluationMode;
+use crate::export::ExportFormat;
+use crate::graph::{validate_monkeys, GraphError};
+use crate::query::Query;
 use std::collections::HashMap;
+use std::fmt;
 use utils::{
     nom::{
         branch::alt,
         bytes::complete::tag,
-        character::complete::{alpha1, space1},
-        combinator::{all_consuming, map},
-        sequence::tuple,
+        character::complete::{alphanumeric1, space0, space1},
+        combinator::{all_consuming, eof, map},
+        sequence::{preceded, terminated, tuple},
         IResult,
     },
     parser::{parse_number_i64, ParseInput},
 };
 
-pub type ParsedOutput = HashMap<MonkeyName, Monkey>;
+pub type ParsedOutput = MonkeyGraph;
 
 #[derive(Default)]
-pub struct Day21 {}
+pub struct Day21 {
+    pub evaluation_mode: EvaluationMode,
+    // What part 2 solves for, root's two sides being equal for humn by default
+    pub query: Query,
+    // Prints the query's equation in this format before solving part 2
+    pub export_format: Option<ExportFormat>,
+}
 
 impl ParseInput for Day21 {
     type ParsedType = ParsedOutput;
     fn parse(&mut self, input: String, _: bool) -> ParsedOutput {
-        let mut parsed_input: HashMap<MonkeyName, Monkey> = HashMap::new();
+        parse_monkeys(&input).unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+// Lines that aren't monkeys are skipped. A monkey waiting on one that isn't in the input, or
+// monkeys waiting on each other, are reported here instead of when the monkeys are evaluated.
+pub fn parse_monkeys(input: &str) -> Result<ParsedOutput, GraphError> {
+    let parsed_lines: Vec<(&str, MonkeyType<&str>)> = input
+        .lines()
+        .filter_map(|l| all_consuming(parse_line)(l).ok().map(|(_, line)| line))
+        .collect();
 
-        input.lines().for_each(|l| {
-            let _ = map(all_consuming(parse_line), |data| {
-                parsed_input.insert(data.monkey_name.to_owned(), data);
-            })(l);
+    // A monkey listed twice keeps its last job, like inserting into a map would
+    let mut ids: HashMap<MonkeyName, MonkeyId> = HashMap::new();
+    let mut names: Vec<MonkeyName> = vec![];
+    let mut jobs: Vec<Option<&MonkeyType<&str>>> = vec![];
+
+    parsed_lines.iter().for_each(|(name, monkey_type)| {
+        let id = *ids.entry(name.to_string()).or_insert_with(|| {
+            names.push(name.to_string());
+            jobs.push(None);
+            names.len() - 1
         });
+        jobs[id] = Some(monkey_type);
+    });
 
-        parsed_input
-    }
+    let get_id = |name: &str, referenced_by: MonkeyId| {
+        ids.get(name)
+            .copied()
+            .ok_or_else(|| GraphError::UnknownMonkey {
+                monkey: name.to_string(),
+                referenced_by: Some(names[referenced_by].to_owned()),
+            })
+    };
+
+    let monkey_types = jobs
+        .iter()
+        .enumerate()
+        .map(|(id, monkey_type)| {
+            Ok(match monkey_type.unwrap() {
+                MonkeyType::Number(n) => MonkeyType::Number(*n),
+                MonkeyType::Other {
+                    operator,
+                    left,
+                    right,
+                } => MonkeyType::Other {
+                    operator: *operator,
+                    left: get_id(left, id)?,
+                    right: get_id(right, id)?,
+                },
+                MonkeyType::Negate(operand) => MonkeyType::Negate(get_id(operand, id)?),
+            })
+        })
+        .collect::<Result<Vec<MonkeyType>, GraphError>>()?;
+
+    let monkeys = MonkeyGraph {
+        names,
+        ids,
+        monkey_types,
+    };
+
+    validate_monkeys(&monkeys)?;
+
+    Ok(monkeys)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -37,25 +102,124 @@ pub enum ArithmeticOperator {
     Subtract,
     Multiply,
     Divide,
+    // Floored, the result has the sign of the divisor
+    Modulo,
+    // The exponent has to be an integer
+    Power,
+    Min,
+    Max,
 }
 
+// Precedence of unary negation, between multiplication and exponentiation
+pub const NEGATION_PRECEDENCE: u8 = 3;
+
+impl ArithmeticOperator {
+    // Higher binds tighter
+    pub fn get_precedence(&self) -> u8 {
+        match self {
+            ArithmeticOperator::Add | ArithmeticOperator::Subtract => 1,
+            ArithmeticOperator::Multiply
+            | ArithmeticOperator::Divide
+            | ArithmeticOperator::Modulo => 2,
+            ArithmeticOperator::Power => 4,
+            ArithmeticOperator::Min | ArithmeticOperator::Max => 5,
+        }
+    }
+
+    // a op (b op c) is the same as (a op b) op c
+    pub fn is_associative(&self) -> bool {
+        matches!(
+            self,
+            ArithmeticOperator::Add
+                | ArithmeticOperator::Multiply
+                | ArithmeticOperator::Min
+                | ArithmeticOperator::Max
+        )
+    }
+
+    // a op b op c is read as a op (b op c)
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, ArithmeticOperator::Power)
+    }
+
+    // Written as min(a, b) instead of between the operands
+    pub fn is_function(&self) -> bool {
+        matches!(self, ArithmeticOperator::Min | ArithmeticOperator::Max)
+    }
+}
+
+impl fmt::Display for ArithmeticOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ArithmeticOperator::Add => "+",
+            ArithmeticOperator::Subtract => "-",
+            ArithmeticOperator::Multiply => "*",
+            ArithmeticOperator::Divide => "/",
+            ArithmeticOperator::Modulo => "%",
+            ArithmeticOperator::Power => "^",
+            ArithmeticOperator::Min => "min",
+            ArithmeticOperator::Max => "max",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+// Monkeys are referred to by id once parsed, the parser fills the names in
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum MonkeyType {
+pub enum MonkeyType<T = MonkeyId> {
     Number(i64),
     Other {
         operator: ArithmeticOperator,
-        left: MonkeyName,
-        right: MonkeyName,
+        left: T,
+        right: T,
     },
+    Negate(T),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Monkey {
-    pub monkey_name: MonkeyName,
-    pub monkey_type: MonkeyType,
+pub type MonkeyName = String;
+
+// Index of the monkey in the graph
+pub type MonkeyId = usize;
+
+// Every name is stored once, monkeys refer to each other by id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonkeyGraph {
+    pub names: Vec<MonkeyName>,
+    pub ids: HashMap<MonkeyName, MonkeyId>,
+    pub monkey_types: Vec<MonkeyType>,
 }
 
-pub type MonkeyName = String;
+impl MonkeyGraph {
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn get_id(&self, name: &str) -> Result<MonkeyId, GraphError> {
+        self.ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| GraphError::UnknownMonkey {
+                monkey: name.to_string(),
+                referenced_by: None,
+            })
+    }
+
+    pub fn get_name(&self, id: MonkeyId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn get_monkey_type(&self, id: MonkeyId) -> &MonkeyType {
+        &self.monkey_types[id]
+    }
+}
+
+fn parse_name(input: &str) -> IResult<&str, &str> {
+    alphanumeric1(input)
+}
 
 fn parse_operation(input: &str) -> IResult<&str, ArithmeticOperator> {
     let (input, operator) = alt((
@@ -63,46 +227,109 @@ fn parse_operation(input: &str) -> IResult<&str, ArithmeticOperator> {
         map(tag("-"), |_| ArithmeticOperator::Subtract),
         map(tag("*"), |_| ArithmeticOperator::Multiply),
         map(tag("/"), |_| ArithmeticOperator::Divide),
+        map(tag("%"), |_| ArithmeticOperator::Modulo),
+        map(tag("^"), |_| ArithmeticOperator::Power),
     ))(input)?;
 
     Ok((input, operator))
 }
 
-fn parse_arithmetic_operation(input: &str) -> IResult<&str, MonkeyType> {
+fn parse_arithmetic_operation(input: &str) -> IResult<&str, MonkeyType<&str>> {
     let (input, (left, _, operator, _, right)) =
-        tuple((alpha1, space1, parse_operation, space1, alpha1))(input)?;
+        tuple((parse_name, space1, parse_operation, space1, parse_name))(input)?;
+
+    Ok((
+        input,
+        MonkeyType::Other {
+            operator,
+            left,
+            right,
+        },
+    ))
+}
+
+fn parse_function(input: &str) -> IResult<&str, MonkeyType<&str>> {
+    // max(pppw, sjmn)
+    let (input, (operator, _, _, left, _, _, _, right, _, _)) = tuple((
+        alt((
+            map(tag("min"), |_| ArithmeticOperator::Min),
+            map(tag("max"), |_| ArithmeticOperator::Max),
+        )),
+        tag("("),
+        space0,
+        parse_name,
+        space0,
+        tag(","),
+        space0,
+        parse_name,
+        space0,
+        tag(")"),
+    ))(input)?;
 
     Ok((
         input,
         MonkeyType::Other {
             operator,
-            left: left.to_string(),
-            right: right.to_string(),
+            left,
+            right,
         },
     ))
 }
 
-fn parse_monkey_type(input: &str) -> IResult<&str, MonkeyType> {
+fn parse_negation(input: &str) -> IResult<&str, MonkeyType<&str>> {
+    // -pppw
+    map(preceded(tag("-"), parse_name), MonkeyType::Negate)(input)
+}
+
+fn parse_monkey_type(input: &str) -> IResult<&str, MonkeyType<&str>> {
+    // A number only when it's the whole job, names can start with digits like in `b2: 2x * humn`
     let (input, monkey_type) = alt((
-        map(parse_number_i64, MonkeyType::Number),
+        map(terminated(parse_number_i64, eof), MonkeyType::Number),
+        parse_function,
+        parse_negation,
         parse_arithmetic_operation,
     ))(input)?;
 
     Ok((input, monkey_type))
 }
 
-fn parse_line(input: &str) -> IResult<&str, Monkey> {
+fn parse_line(input: &str) -> IResult<&str, (&str, MonkeyType<&str>)> {
     // root: pppw + sjmn
     // dvpt: 3
+    // m2x: min(pppw, sjmn)
+    // neg1: -dvpt
 
     let (input, (monkey_name, _, monkey_type)) =
-        tuple((alpha1, tag(": "), parse_monkey_type))(input)?;
+        tuple((parse_name, tag(": "), parse_monkey_type))(input)?;
 
-    Ok((
-        input,
-        Monkey {
-            monkey_name: monkey_name.to_string(),
-            monkey_type,
-        },
-    ))
+    Ok((input, (monkey_name, monkey_type)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_can_start_with_digits() {
+        let monkeys =
+            parse_monkeys("root: b2 + 3x\nb2: 2x * humn\n2x: 2\nhumn: 5\n3x: -2x\n").unwrap();
+
+        let b2 = monkeys.get_id("b2").unwrap();
+        assert_eq!(
+            monkeys.get_monkey_type(b2),
+            &MonkeyType::Other {
+                operator: ArithmeticOperator::Multiply,
+                left: monkeys.get_id("2x").unwrap(),
+                right: monkeys.get_id("humn").unwrap(),
+            }
+        );
+        assert_eq!(
+            monkeys.get_monkey_type(monkeys.get_id("2x").unwrap()),
+            &MonkeyType::Number(2)
+        );
+        assert_eq!(
+            monkeys.get_monkey_type(monkeys.get_id("3x").unwrap()),
+            &MonkeyType::Negate(monkeys.get_id("2x").unwrap())
+        );
+    }
 }
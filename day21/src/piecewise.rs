@@ -0,0 +1,556 @@
+use crate::evaluation::apply_operator;
+use crate::expression::{Expression, ExpressionError, Polynomial, Solution};
+use crate::graph::get_evaluation_order;
+use crate::inequality::IntegerRange;
+use crate::parser::{ArithmeticOperator, MonkeyGraph, MonkeyId, MonkeyType};
+use crate::query::Relation;
+use num_bigint::BigInt;
+use num_integer::{ExtendedGcd, Integer};
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::fmt;
+
+// Min, max and modulo split the unknown into cases, a monkey splitting it into more than this
+// many is given up on
+pub const MAX_CASES: usize = 10_000;
+
+// Integers in `range` that leave `residue` when divided by `modulus`. The ends of the range are
+// always in the class themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegerClass {
+    pub range: IntegerRange,
+    pub residue: BigInt,
+    pub modulus: BigInt,
+}
+
+// 5, 3..=7, 4k + 1 and 4k + 1 in 5..=41
+impl fmt::Display for IntegerClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let is_bounded = self.range.start.is_some() || self.range.end.is_some();
+        if !self.modulus.is_one() {
+            write!(f, "{}k + {}", self.modulus, self.residue)?;
+            if !is_bounded {
+                return Ok(());
+            }
+            write!(f, " in ")?;
+        }
+        match (&self.range.start, &self.range.end) {
+            (Some(start), Some(end)) if start == end => write!(f, "{}", start),
+            _ => write!(f, "{}", self.range),
+        }
+    }
+}
+
+impl IntegerClass {
+    pub fn all() -> IntegerClass {
+        IntegerClass {
+            range: IntegerRange {
+                start: None,
+                end: None,
+            },
+            residue: BigInt::zero(),
+            modulus: BigInt::one(),
+        }
+    }
+
+    pub fn point(x: BigInt) -> IntegerClass {
+        IntegerClass {
+            range: IntegerRange {
+                start: Some(x.clone()),
+                end: Some(x),
+            },
+            residue: BigInt::zero(),
+            modulus: BigInt::one(),
+        }
+    }
+
+    // Moves the ends of the range into the class, None when nothing is left in it
+    pub fn new(range: IntegerRange, residue: BigInt, modulus: BigInt) -> Option<IntegerClass> {
+        let residue = residue.mod_floor(&modulus);
+        let start = range
+            .start
+            .map(|start| &start + (&residue - &start).mod_floor(&modulus));
+        let end = range
+            .end
+            .map(|end| &end - (&end - &residue).mod_floor(&modulus));
+        if start
+            .as_ref()
+            .zip(end.as_ref())
+            .is_some_and(|(start, end)| start > end)
+        {
+            return None;
+        }
+
+        Some(IntegerClass {
+            range: IntegerRange { start, end },
+            residue,
+            modulus,
+        })
+    }
+
+    pub fn contains(&self, x: &BigInt) -> bool {
+        x.mod_floor(&self.modulus) == self.residue
+            && self.range.start.as_ref().is_none_or(|start| start <= x)
+            && self.range.end.as_ref().is_none_or(|end| x <= end)
+    }
+
+    // Number of integers in the class, None when there are infinitely many
+    pub fn len(&self) -> Option<BigInt> {
+        let (start, end) = (self.range.start.as_ref()?, self.range.end.as_ref()?);
+        Some((end - start) / &self.modulus + BigInt::one())
+    }
+
+    pub fn intersect(&self, other: &IntegerClass) -> Option<IntegerClass> {
+        // Chinese remainder theorem: x = r1 + m1 * t with m1 * t = r2 - r1 modulo m2
+        let ExtendedGcd { gcd, x, .. } = self.modulus.extended_gcd(&other.modulus);
+        let difference = &other.residue - &self.residue;
+        if !difference.is_multiple_of(&gcd) {
+            return None;
+        }
+        let residue = &self.residue + &self.modulus * (difference / &gcd * x);
+        let modulus = &self.modulus / &gcd * &other.modulus;
+
+        let start = match (&self.range.start, &other.range.start) {
+            (Some(a), Some(b)) => Some(a.max(b).clone()),
+            (a, b) => a.clone().or_else(|| b.clone()),
+        };
+        let end = match (&self.range.end, &other.range.end) {
+            (Some(a), Some(b)) => Some(a.min(b).clone()),
+            (a, b) => a.clone().or_else(|| b.clone()),
+        };
+
+        IntegerClass::new(IntegerRange { start, end }, residue, modulus)
+    }
+}
+
+impl Solution {
+    // Every integer in the solution, as classes that don't overlap
+    pub fn to_classes(&self) -> Vec<IntegerClass> {
+        match self {
+            Solution::Values(values) => values.iter().cloned().map(IntegerClass::point).collect(),
+            Solution::InfinitelyMany => vec![IntegerClass::all()],
+            Solution::NoIntegerSolution(_) => vec![],
+            Solution::Ranges(ranges) => ranges
+                .iter()
+                .map(|range| IntegerClass {
+                    range: range.clone(),
+                    residue: BigInt::zero(),
+                    modulus: BigInt::one(),
+                })
+                .collect(),
+            Solution::Classes(classes) => classes.clone(),
+        }
+    }
+
+    // Values or ranges when no class skips integers
+    pub fn from_classes(classes: Vec<IntegerClass>) -> Solution {
+        if classes.iter().any(|class| !class.modulus.is_one()) {
+            return Solution::Classes(classes);
+        }
+
+        let mut ranges: Vec<IntegerRange> = classes.into_iter().map(|class| class.range).collect();
+        ranges.sort_by(|a, b| a.start.cmp(&b.start));
+
+        if !ranges.is_empty()
+            && ranges
+                .iter()
+                .all(|range| range.start.is_some() && range.start == range.end)
+        {
+            let mut values: Vec<BigInt> =
+                ranges.into_iter().filter_map(|range| range.start).collect();
+            values.dedup();
+            return Solution::Values(values);
+        }
+
+        // Ranges that overlap or touch are merged
+        let mut merged: Vec<IntegerRange> = vec![];
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last)
+                    if last.end.as_ref().is_none_or(|end| {
+                        range
+                            .start
+                            .as_ref()
+                            .is_none_or(|start| start <= &(end + BigInt::one()))
+                    }) =>
+                {
+                    last.end = match (&last.end, &range.end) {
+                        (Some(a), Some(b)) => Some(a.max(b).clone()),
+                        _ => None,
+                    };
+                }
+                _ => merged.push(range),
+            }
+        }
+        Solution::Ranges(merged)
+    }
+}
+
+// What a monkey shouts in every case min, max and modulo split the unknown into. Integers that
+// aren't in any of the classes divide by zero somewhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piecewise(pub Vec<(IntegerClass, Expression)>);
+
+impl Piecewise {
+    pub fn new(expression: Expression) -> Piecewise {
+        Piecewise(vec![(IntegerClass::all(), expression)])
+    }
+
+    pub fn negate(&self) -> Piecewise {
+        Piecewise(
+            self.0
+                .iter()
+                .map(|(class, expression)| (class.clone(), expression.negate()))
+                .collect(),
+        )
+    }
+
+    // Applies the operator in every pair of cases that overlap. min(a, b) is a where a <= b and
+    // b everywhere else, max(a, b) the other way around. Errors name `monkey_name` as the
+    // monkey applying the operator.
+    pub fn apply(
+        &self,
+        operator: ArithmeticOperator,
+        other: &Piecewise,
+        monkey_name: &str,
+    ) -> Result<Piecewise, ExpressionError> {
+        let mut pieces = vec![];
+
+        for (a_class, a) in self.0.iter() {
+            for (b_class, b) in other.0.iter() {
+                let Some(class) = a_class.intersect(b_class) else {
+                    continue;
+                };
+                let is_constant = a.as_constant().is_some() && b.as_constant().is_some();
+
+                match operator {
+                    ArithmeticOperator::Min | ArithmeticOperator::Max if !is_constant => {
+                        let (lower, upper) = match operator {
+                            ArithmeticOperator::Min => (a, b),
+                            _ => (b, a),
+                        };
+                        let mut a_wins = lower.solve(Relation::LessThan, upper)?.to_classes();
+                        a_wins.extend(lower.solve_equal(upper)?.to_classes());
+                        let b_wins = lower.solve(Relation::GreaterThan, upper)?.to_classes();
+
+                        for (wins, expression) in [(a_wins, a), (b_wins, b)] {
+                            pieces.extend(wins.iter().filter_map(|wins| {
+                                Some((wins.intersect(&class)?, expression.clone()))
+                            }));
+                        }
+                    }
+                    ArithmeticOperator::Modulo if a.as_constant().is_none() => {
+                        pieces.extend(split_modulo(&class, a, b, monkey_name)?)
+                    }
+                    _ => pieces.push((class, a.apply(operator, b, monkey_name)?)),
+                }
+
+                if pieces.len() > MAX_CASES {
+                    return Err(ExpressionError::TooManyCases(monkey_name.to_string()));
+                }
+            }
+        }
+
+        Ok(Piecewise(pieces))
+    }
+
+    // Solves the relation in every pair of cases that overlap and keeps the solutions that are
+    // in both
+    pub fn solve(
+        &self,
+        relation: Relation,
+        other: &Piecewise,
+    ) -> Result<Solution, ExpressionError> {
+        // Nothing was split, so the solution keeps its exact roots
+        if let ([(a_class, a)], [(b_class, b)]) = (self.0.as_slice(), other.0.as_slice()) {
+            if *a_class == IntegerClass::all() && *b_class == IntegerClass::all() {
+                return a.solve(relation, b);
+            }
+        }
+
+        let mut classes = vec![];
+        for (a_class, a) in self.0.iter() {
+            for (b_class, b) in other.0.iter() {
+                let Some(class) = a_class.intersect(b_class) else {
+                    continue;
+                };
+                classes.extend(
+                    a.solve(relation, b)?
+                        .to_classes()
+                        .iter()
+                        .filter_map(|solution| solution.intersect(&class)),
+                );
+            }
+        }
+
+        Ok(Solution::from_classes(classes))
+    }
+}
+
+// a % m for a polynomial a and a constant m. Adding `period` to the unknown changes a by a
+// multiple of m, so a % m only depends on the unknown's residue and is a constant for each one.
+fn split_modulo(
+    class: &IntegerClass,
+    a: &Expression,
+    m: &Expression,
+    monkey_name: &str,
+) -> Result<Vec<(IntegerClass, Expression)>, ExpressionError> {
+    let not_polynomial = || ExpressionError::NotPolynomial {
+        monkey: monkey_name.to_string(),
+        operator: ArithmeticOperator::Modulo,
+    };
+    let divisor = m.as_constant().ok_or_else(not_polynomial)?;
+    let denominator = a.denominator.as_constant().ok_or_else(not_polynomial)?;
+    if divisor.is_zero() {
+        return Err(ExpressionError::DivisionByZero(monkey_name.to_string()));
+    }
+
+    let polynomial = a.numerator.scale(&(BigRational::one() / denominator));
+    let period = polynomial
+        .0
+        .iter()
+        .fold(BigInt::one(), |lcm, c| lcm.lcm(c.denom()))
+        * divisor.numer().abs();
+
+    // Either every integer in the class on its own, or one class for every residue modulo the
+    // period
+    let residues = &period / class.modulus.gcd(&period);
+    let classes: Vec<IntegerClass> = match class.len().filter(|len| len <= &residues) {
+        Some(len) => {
+            check_cases(&len, monkey_name)?;
+            let start = class.range.start.clone().unwrap();
+            (0..len.to_usize().unwrap())
+                .map(|i| IntegerClass::point(&start + &class.modulus * BigInt::from(i)))
+                .collect()
+        }
+        None => {
+            check_cases(&residues, monkey_name)?;
+            let modulus = &class.modulus * &residues;
+            (0..residues.to_usize().unwrap())
+                .filter_map(|i| {
+                    IntegerClass::new(
+                        class.range.clone(),
+                        &class.residue + &class.modulus * BigInt::from(i),
+                        modulus.clone(),
+                    )
+                })
+                .collect()
+        }
+    };
+
+    classes
+        .into_iter()
+        .map(|class| {
+            // Single integers have no residue to go by
+            let x = BigRational::from_integer(match &class.range.start {
+                Some(start) if class.modulus.is_one() => start.clone(),
+                _ => class.residue.clone(),
+            });
+            let value = apply_operator(
+                ArithmeticOperator::Modulo,
+                &polynomial.evaluate(&x),
+                &divisor,
+            )
+            .map_err(|err| ExpressionError::from_operator_error(err, monkey_name))?;
+            let expression = Expression {
+                numerator: Polynomial::constant(value),
+                denominator: Polynomial::constant(BigRational::one()),
+                undefined_at: a.undefined_at.clone(),
+            };
+            Ok((class, expression))
+        })
+        .collect()
+}
+
+fn check_cases(cases: &BigInt, monkey_name: &str) -> Result<(), ExpressionError> {
+    if cases > &BigInt::from(MAX_CASES) {
+        return Err(ExpressionError::TooManyCases(monkey_name.to_string()));
+    }
+    Ok(())
+}
+
+// What the monkey shouts in every case, with the number of every monkey in `unknowns` left as
+// the same unknown
+pub fn build_piecewise(
+    monkeys: &MonkeyGraph,
+    monkey: MonkeyId,
+    unknowns: &[MonkeyId],
+) -> Result<Piecewise, ExpressionError> {
+    let mut pieces: Vec<Option<Piecewise>> = vec![None; monkeys.len()];
+
+    for id in get_evaluation_order(monkeys, monkey, unknowns)? {
+        let piecewise = match monkeys.get_monkey_type(id) {
+            _ if unknowns.contains(&id) => Piecewise::new(Expression::unknown()),
+            MonkeyType::Number(n) => Piecewise::new(Expression::constant(
+                BigRational::from_integer(BigInt::from(*n)),
+            )),
+            MonkeyType::Other {
+                operator,
+                left,
+                right,
+            } => pieces[*left].as_ref().unwrap().apply(
+                *operator,
+                pieces[*right].as_ref().unwrap(),
+                monkeys.get_name(id),
+            )?,
+            MonkeyType::Negate(operand) => pieces[*operand].as_ref().unwrap().negate(),
+        };
+        pieces[id] = Some(piecewise);
+    }
+
+    Ok(pieces[monkey].take().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::{evaluate, EvaluationMode};
+    use crate::parser::parse_monkeys;
+    use crate::query::Query;
+    use std::cmp::Ordering;
+
+    // Solves root's two sides for humn and checks the solution against evaluating the monkeys
+    // for every humn in -60..=60
+    fn assert_matches_brute_force(jobs: &str, relation: Relation) -> Solution {
+        let monkeys = parse_monkeys(&format!("{}\nhumn: 0", jobs)).unwrap();
+        let query = Query {
+            relation,
+            ..Default::default()
+        };
+        let solution = query.solve(&monkeys).unwrap();
+        let classes = solution.to_classes();
+
+        for x in -60..=60 {
+            let monkeys = parse_monkeys(&format!("{}\nhumn: {}", jobs, x)).unwrap();
+            let [left, right] =
+                ["lhs", "rhs"].map(|name| evaluate(&monkeys, name, EvaluationMode::Exact).ok());
+            let expected = left.zip(right).is_some_and(|(left, right)| {
+                left.cmp(&right)
+                    == match relation {
+                        Relation::Equal => Ordering::Equal,
+                        Relation::LessThan => Ordering::Less,
+                        Relation::GreaterThan => Ordering::Greater,
+                    }
+            });
+            let x = BigInt::from(x);
+            assert_eq!(
+                classes.iter().any(|class| class.contains(&x)),
+                expected,
+                "{} at {} for {}",
+                solution,
+                x,
+                jobs
+            );
+        }
+
+        solution
+    }
+
+    #[test]
+    fn min_and_max_split_into_cases() {
+        let solution = assert_matches_brute_force(
+            "root: lhs + rhs\nlhs: min(humn, ten)\nten: 10\nrhs: 7",
+            Relation::Equal,
+        );
+        assert_eq!(solution.to_string(), "7");
+
+        let solution = assert_matches_brute_force(
+            "root: lhs + rhs\nlhs: min(humn, ten)\nten: 10\nrhs: 10",
+            Relation::Equal,
+        );
+        assert_eq!(solution.to_string(), "10..");
+
+        // max(x^2, 2x + 3) = 9 at -3 and 3, min of that with 20 - x
+        let jobs = "root: lhs + rhs\nlhs: min(m, d)\nm: max(sq, lin)\nsq: humn ^ two\n\
+                    lin: dbl + three\ndbl: two * humn\nd: twenty - humn\ntwo: 2\nthree: 3\ntwenty: 20\n\
+                    rhs: 9";
+        for relation in [Relation::Equal, Relation::LessThan, Relation::GreaterThan] {
+            assert_matches_brute_force(jobs, relation);
+        }
+        assert_eq!(
+            assert_matches_brute_force(jobs, Relation::Equal).to_string(),
+            "-3 or 3 or 11"
+        );
+    }
+
+    #[test]
+    fn modulo_gives_residue_classes() {
+        let solution = assert_matches_brute_force(
+            "root: lhs + rhs\nlhs: humn % seven\nseven: 7\nrhs: 3",
+            Relation::Equal,
+        );
+        assert_eq!(solution.to_string(), "7k + 3");
+
+        // 2x + 1 is 3 modulo 4 for every odd x
+        let solution = assert_matches_brute_force(
+            "root: lhs + rhs\nlhs: a % four\na: dbl + one\ndbl: two * humn\nfour: 4\ntwo: 2\none: 1\n\
+             rhs: 3",
+            Relation::Equal,
+        );
+        assert_eq!(solution.to_string(), "4k + 1 or 4k + 3");
+
+        // Fractions, negative divisors and modulo inside min
+        let jobs = "root: lhs + rhs\nlhs: min(r, humn)\nr: q % five\nq: p / three\n\
+                    p: humn * humn\nthree: 3\nfive: -5\nrhs: 0";
+        for relation in [Relation::Equal, Relation::LessThan, Relation::GreaterThan] {
+            assert_matches_brute_force(jobs, relation);
+        }
+
+        // Inside a range the integers are split one by one
+        let jobs = "root: lhs + rhs\nlhs: c % big\nc: min(m, hundred)\nm: max(humn, zero)\n\
+                    big: 1000003\nzero: 0\nhundred: 100\nrhs: 0";
+        assert_eq!(
+            assert_matches_brute_force(jobs, Relation::Equal).to_string(),
+            "..=0"
+        );
+    }
+
+    #[test]
+    fn modulo_of_the_unknown_by_the_unknown_is_not_polynomial() {
+        let monkeys =
+            parse_monkeys("root: lhs + rhs\nlhs: seven % humn\nseven: 7\nrhs: 3\nhumn: 0").unwrap();
+        assert_eq!(
+            Query::default().solve(&monkeys),
+            Err(ExpressionError::NotPolynomial {
+                monkey: "lhs".to_string(),
+                operator: ArithmeticOperator::Modulo,
+            })
+        );
+    }
+
+    #[test]
+    fn huge_modulus_is_too_many_cases() {
+        let monkeys =
+            parse_monkeys("root: lhs + rhs\nlhs: humn % big\nbig: 1000003\nrhs: 3\nhumn: 0")
+                .unwrap();
+        assert_eq!(
+            Query::default().solve(&monkeys),
+            Err(ExpressionError::TooManyCases("lhs".to_string()))
+        );
+    }
+
+    #[test]
+    fn classes_intersect() {
+        let class = |start: i64, end: i64, residue: i64, modulus: i64| {
+            IntegerClass::new(
+                IntegerRange {
+                    start: Some(BigInt::from(start)),
+                    end: Some(BigInt::from(end)),
+                },
+                BigInt::from(residue),
+                BigInt::from(modulus),
+            )
+        };
+        let a = class(-50, 50, 1, 4).unwrap();
+        let b = class(-20, 80, 5, 6).unwrap();
+        let both = a.intersect(&b).unwrap();
+        for x in -100..=100 {
+            let x = BigInt::from(x);
+            assert_eq!(both.contains(&x), a.contains(&x) && b.contains(&x), "{}", x);
+        }
+        assert_eq!(both.to_string(), "12k + 5 in -19..=41");
+
+        assert_eq!(a.intersect(&class(-50, 50, 0, 2).unwrap()), None);
+        assert_eq!(class(1, 3, 0, 7), None);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::expression::{ExpressionError, Solution};
+use crate::parser::{MonkeyGraph, MonkeyId, MonkeyName, MonkeyType};
+use crate::piecewise::build_piecewise;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Relation {
+    #[default]
+    Equal,
+    LessThan,
+    GreaterThan,
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Relation::Equal => "=",
+            Relation::LessThan => "<",
+            Relation::GreaterThan => ">",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+// Which numbers make the two sides of `target` satisfy `relation`. The target's own operator
+// is ignored, like root's in the puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub target: MonkeyName,
+    // Monkeys whose number is replaced by the unknown, they all shout the same number
+    pub unknowns: Vec<MonkeyName>,
+    pub relation: Relation,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Query {
+            target: "root".to_string(),
+            unknowns: vec!["humn".to_string()],
+            relation: Relation::Equal,
+        }
+    }
+}
+
+impl Query {
+    // Ids of the target, its two sides and the unknowns
+    pub fn get_ids(
+        &self,
+        monkeys: &MonkeyGraph,
+    ) -> Result<(MonkeyId, MonkeyId, MonkeyId, Vec<MonkeyId>), ExpressionError> {
+        let target = monkeys.get_id(&self.target)?;
+        let unknowns = self
+            .unknowns
+            .iter()
+            .map(|unknown| monkeys.get_id(unknown))
+            .collect::<Result<Vec<MonkeyId>, _>>()?;
+
+        match monkeys.get_monkey_type(target) {
+            MonkeyType::Number(_) | MonkeyType::Negate(_) => {
+                Err(ExpressionError::NothingToCompare(self.target.clone()))
+            }
+            MonkeyType::Other { left, right, .. } => Ok((target, *left, *right, unknowns)),
+        }
+    }
+
+    pub fn solve(&self, monkeys: &MonkeyGraph) -> Result<Solution, ExpressionError> {
+        let (_, left, right, unknowns) = self.get_ids(monkeys)?;
+        let left = build_piecewise(monkeys, left, &unknowns)?;
+        let right = build_piecewise(monkeys, right, &unknowns)?;
+        left.solve(self.relation, &right)
+    }
+}
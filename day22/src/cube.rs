@@ -0,0 +1,449 @@
+use crate::parser::{Direction, ParsedOutput, Points};
+use crate::part1::WrapPointState;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+// Directions on the cube, every axis is one of the unit vectors or its opposite
+type Vector = [i32; 3];
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+fn negate(vector: Vector) -> Vector {
+    vector.map(|v| -v)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CubeError {
+    // The number of cells on the map isn't 6 times a square
+    InvalidCellCount(usize),
+    // Face sized tiles of the map that have cells, when there aren't exactly 6
+    NotSixFaces(usize),
+    // Some faces can't be reached from the others
+    NotConnected,
+    // Two faces land on the same side of the cube once folded, with their tiles
+    OverlappingFaces((usize, usize), (usize, usize)),
+}
+
+impl fmt::Display for CubeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CubeError::InvalidCellCount(count) => write!(
+                f,
+                "The map has {} cells, a cube net has 6 square faces.",
+                count
+            ),
+            CubeError::NotSixFaces(count) => {
+                write!(f, "The map has {} faces, a cube net has 6.", count)
+            }
+            CubeError::NotConnected => write!(f, "The faces of the map aren't connected."),
+            CubeError::OverlappingFaces(first, second) => write!(
+                f,
+                "The faces at tiles {:?} and {:?} fold onto the same side of the cube.",
+                first, second
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CubeError {}
+
+// One face of the net and where it ends up on the cube. `right` and `down` are where east and
+// south on the map point once folded, `normal` points out of the cube.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CubeFace {
+    // Position of the face in face sized tiles, from the top left of the map
+    pub tile: (usize, usize),
+    pub normal: Vector,
+    pub right: Vector,
+    pub down: Vector,
+}
+
+impl CubeFace {
+    // Where moving in the direction on the map points once folded
+    fn get_vector(&self, direction: Direction) -> Vector {
+        match direction {
+            Direction::North => negate(self.down),
+            Direction::South => self.down,
+            Direction::East => self.right,
+            Direction::West => negate(self.right),
+        }
+    }
+
+    // Side of the cube the face ends up on, seen from above with south on the map towards the
+    // front
+    pub fn get_label(&self) -> &'static str {
+        match self.normal {
+            [0, 0, 1] => "Top",
+            [0, 0, -1] => "Bottom",
+            [1, 0, 0] => "Right",
+            [-1, 0, 0] => "Left",
+            [0, 1, 0] => "Front",
+            _ => "Back",
+        }
+    }
+
+    // The face next to this one on the map in the direction, folded over the shared edge
+    fn roll(&self, direction: Direction, tile: (usize, usize)) -> CubeFace {
+        let normal = self.get_vector(direction);
+        let (right, down) = match direction {
+            Direction::North => (self.right, self.normal),
+            Direction::South => (self.right, negate(self.normal)),
+            Direction::East => (negate(self.normal), self.down),
+            Direction::West => (self.normal, self.down),
+        };
+        CubeFace {
+            tile,
+            normal,
+            right,
+            down,
+        }
+    }
+}
+
+// The map folded into a cube. Works for any of the 11 nets, in any rotation or mirror image,
+// with any face size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CubeNet {
+    pub face_size: usize,
+    pub faces: Vec<CubeFace>,
+}
+
+impl CubeNet {
+    pub fn fold(parsed_input: &ParsedOutput) -> Result<CubeNet, CubeError> {
+        let cell_count = parsed_input
+            .map
+            .iter()
+            .flatten()
+            .filter(|point| !matches!(point, Points::OutOfBounds))
+            .count();
+        let face_size = (1..)
+            .take_while(|size| 6 * size * size <= cell_count)
+            .last()
+            .filter(|size| 6 * size * size == cell_count)
+            .ok_or(CubeError::InvalidCellCount(cell_count))?;
+
+        // Faces have to fill whole tiles, so with the right cell count there are 6 of them
+        let width = parsed_input
+            .map
+            .iter()
+            .map(|row| row.len())
+            .max()
+            .unwrap_or(0);
+        let height = parsed_input.map.len();
+        let tiles: Vec<(usize, usize)> = (0..height / face_size + 1)
+            .flat_map(|y| (0..width / face_size + 1).map(move |x| (x, y)))
+            .filter(|(x, y)| {
+                let (x, y) = (1 + x * face_size, 1 + y * face_size);
+                (0..face_size).all(|i| {
+                    (0..face_size).all(|j| {
+                        !matches!(parsed_input.get_point((x + i, y + j)), Points::OutOfBounds)
+                    })
+                })
+            })
+            .collect();
+        if tiles.len() != 6 {
+            return Err(CubeError::NotSixFaces(tiles.len()));
+        }
+
+        // Rolls the cube over the map from the first face, like folding the net one edge at a
+        // time
+        let mut faces: Vec<CubeFace> = vec![CubeFace {
+            tile: tiles[0],
+            normal: [0, 0, 1],
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+        }];
+        let mut queue = VecDeque::from([0]);
+
+        while let Some(index) = queue.pop_front() {
+            let face = faces[index];
+            for direction in DIRECTIONS {
+                let Some(tile) = get_next_tile(face.tile, direction) else {
+                    continue;
+                };
+                if !tiles.contains(&tile) || faces.iter().any(|face| face.tile == tile) {
+                    continue;
+                }
+
+                let next = face.roll(direction, tile);
+                if let Some(other) = faces.iter().find(|face| face.normal == next.normal) {
+                    return Err(CubeError::OverlappingFaces(other.tile, tile));
+                }
+                faces.push(next);
+                queue.push_back(faces.len() - 1);
+            }
+        }
+
+        if faces.len() != 6 {
+            return Err(CubeError::NotConnected);
+        }
+
+        Ok(CubeNet { face_size, faces })
+    }
+
+    pub fn get_face(&self, tile: (usize, usize)) -> Option<&CubeFace> {
+        self.faces.iter().find(|face| face.tile == tile)
+    }
+
+    // Face the cell is on
+    pub fn get_face_at(&self, (x, y): (usize, usize)) -> Option<&CubeFace> {
+        if x == 0 || y == 0 {
+            return None;
+        }
+        self.get_face(((x - 1) / self.face_size, (y - 1) / self.face_size))
+    }
+
+    // Face the walker ends up on when leaving `face` in the direction, and the side of that face
+    // it comes in through
+    pub fn get_neighbour(&self, face: &CubeFace, direction: Direction) -> (&CubeFace, Direction) {
+        let normal = face.get_vector(direction);
+        let neighbour = self
+            .faces
+            .iter()
+            .find(|other| other.normal == normal)
+            .unwrap();
+        let side = DIRECTIONS
+            .into_iter()
+            .find(|side| neighbour.get_vector(*side) == face.normal)
+            .unwrap();
+        (neighbour, side)
+    }
+
+    // Cells along a side of the face, going clockwise around it. Two sides that are glued
+    // together go along their shared edge in opposite directions.
+    pub fn get_edge_cell(&self, face: &CubeFace, side: Direction, i: usize) -> (usize, usize) {
+        let last = self.face_size - 1;
+        let (x, y) = (
+            1 + face.tile.0 * self.face_size,
+            1 + face.tile.1 * self.face_size,
+        );
+        match side {
+            Direction::North => (x + i, y),
+            Direction::East => (x + last, y + i),
+            Direction::South => (x + last - i, y + last),
+            Direction::West => (x, y + last - i),
+        }
+    }
+
+    // Where walking off every cell on the outline of the map lands, and facing which way. The 14
+    // sides on the outline are glued together in pairs.
+    pub fn get_stitches(&self) -> HashMap<WrapPointState, WrapPointState> {
+        let mut stitches = HashMap::new();
+
+        for face in self.faces.iter() {
+            for direction in DIRECTIONS {
+                let is_inside = get_next_tile(face.tile, direction)
+                    .is_some_and(|tile| self.get_face(tile).is_some());
+                if is_inside {
+                    continue;
+                }
+
+                let (neighbour, side) = self.get_neighbour(face, direction);
+                for i in 0..self.face_size {
+                    stitches.insert(
+                        WrapPointState(self.get_edge_cell(face, direction, i), direction),
+                        WrapPointState(
+                            self.get_edge_cell(neighbour, side, self.face_size - 1 - i),
+                            side.opposite(),
+                        ),
+                    );
+                }
+            }
+        }
+
+        stitches
+    }
+}
+
+fn get_next_tile((x, y): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+    match direction {
+        Direction::North => Some((x, y.checked_sub(1)?)),
+        Direction::South => Some((x, y + 1)),
+        Direction::East => Some((x + 1, y)),
+        Direction::West => Some((x.checked_sub(1)?, y)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Instructions, Problem};
+    use crate::part1::{get_new_pos, WrapFunction};
+    use crate::validation::StitchReport;
+
+    // The 11 ways to unfold a cube, one face per `#`. Every other net is one of these rotated or
+    // mirrored.
+    const CUBE_NETS: [&[&str]; 11] = [
+        &["#...", "####", "#..."],
+        &["#...", "####", ".#.."],
+        &["#...", "####", "..#."],
+        &["#...", "####", "...#"],
+        &[".#..", "####", ".#.."],
+        &[".#..", "####", "..#."],
+        &["##..", ".###", ".#.."],
+        &["##..", ".###", "..#."],
+        &["##..", ".###", "...#"],
+        &["##..", ".##.", "..##"],
+        &["###..", "..###"],
+    ];
+
+    const FACE_SIZES: [usize; 5] = [1, 2, 3, 4, 7];
+
+    // The net's tiles turned a quarter clockwise `rotations` times, then mirrored left to right
+    fn transform_net(net: &[&str], rotations: usize, mirrored: bool) -> Vec<String> {
+        let mut tiles: Vec<Vec<char>> = net.iter().map(|row| row.chars().collect()).collect();
+
+        for _ in 0..rotations {
+            let height = tiles.len();
+            let width = tiles[0].len();
+            tiles = (0..width)
+                .map(|x| (0..height).rev().map(|y| tiles[y][x]).collect())
+                .collect();
+        }
+        if mirrored {
+            tiles.iter_mut().for_each(|row| row.reverse());
+        }
+
+        tiles
+            .into_iter()
+            .map(|row| row.into_iter().collect())
+            .collect()
+    }
+
+    // All 11 nets in all 8 of their rotations and mirror images
+    fn get_all_nets() -> Vec<Vec<String>> {
+        CUBE_NETS
+            .iter()
+            .flat_map(|net| {
+                (0..4).flat_map(move |rotations| {
+                    [false, true].map(|mirrored| transform_net(net, rotations, mirrored))
+                })
+            })
+            .collect()
+    }
+
+    // Open map with every tile of the net blown up to a face of the given size, laid out the way
+    // the parser lays out the input
+    fn build_net_map(net: &[String], face_size: usize) -> ParsedOutput {
+        let mut map = vec![vec![]];
+
+        for row in net.iter() {
+            for _ in 0..face_size {
+                let mut line = vec![Points::OutOfBounds];
+                for tile in row.chars() {
+                    let point = if tile == '#' {
+                        Points::Empty
+                    } else {
+                        Points::OutOfBounds
+                    };
+                    line.extend(std::iter::repeat_n(point, face_size));
+                }
+                map.push(line);
+            }
+        }
+
+        ParsedOutput {
+            instructions: Vec::<Instructions>::new(),
+            map,
+        }
+    }
+
+    fn fold_net(net: &[String], face_size: usize) -> (ParsedOutput, CubeNet) {
+        let parsed_input = build_net_map(net, face_size);
+        let cube = CubeNet::fold(&parsed_input)
+            .unwrap_or_else(|err| panic!("Folding {:?} failed: {}", net, err));
+        (parsed_input, cube)
+    }
+
+    #[test]
+    fn every_net_folds_onto_six_sides() {
+        let nets = get_all_nets();
+        assert_eq!(nets.len(), 88);
+
+        for face_size in FACE_SIZES {
+            for net in nets.iter() {
+                let (_, cube) = fold_net(net, face_size);
+                assert_eq!(cube.face_size, face_size, "{:?}", net);
+
+                let mut labels: Vec<&str> =
+                    cube.faces.iter().map(|face| face.get_label()).collect();
+                labels.sort();
+                assert_eq!(
+                    labels,
+                    ["Back", "Bottom", "Front", "Left", "Right", "Top"],
+                    "{:?}",
+                    net
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_net_has_consistent_stitches() {
+        for face_size in FACE_SIZES {
+            for net in get_all_nets() {
+                let (parsed_input, cube) = fold_net(&net, face_size);
+                let report = StitchReport::new(&parsed_input, &cube.get_stitches(), face_size);
+                assert!(report.is_valid(), "Stitching {:?} failed. {}", net, report);
+                // A net's outline has 14 sides
+                assert_eq!(report.edges, 14, "{:?}", net);
+            }
+        }
+    }
+
+    // On a cube, walking straight for 4 faces comes back to the start facing the same way. Tried
+    // from every cell on the outline of the net in every direction that leaves it.
+    #[test]
+    fn walking_four_faces_comes_back_to_the_start() {
+        for face_size in FACE_SIZES {
+            for net in get_all_nets() {
+                let (parsed_input, cube) = fold_net(&net, face_size);
+                let stitches = cube.get_stitches();
+                let starts: Vec<WrapPointState> = stitches.keys().copied().collect();
+
+                let get_warped_point: WrapFunction =
+                    Box::new(move |point, _, direction: Direction| {
+                        *stitches.get(&WrapPointState(point, direction)).unwrap()
+                    });
+
+                for start in starts {
+                    let problem = Problem {
+                        position: start.0,
+                        direction: start.1,
+                        ..Default::default()
+                    };
+                    let end = get_new_pos(
+                        &problem,
+                        &parsed_input,
+                        4 * face_size as u32,
+                        &get_warped_point,
+                    );
+                    assert_eq!(end, start, "{:?} with faces of size {}", net, face_size);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn maps_that_are_not_nets_are_rejected() {
+        let fold = |net: &[&str], face_size: usize| {
+            let net: Vec<String> = net.iter().map(|row| row.to_string()).collect();
+            CubeNet::fold(&build_net_map(&net, face_size))
+        };
+
+        assert_eq!(
+            fold(&["###", "###"], 2),
+            Err(CubeError::OverlappingFaces((0, 1), (1, 1)))
+        );
+        assert_eq!(fold(&["#####."], 2), Err(CubeError::InvalidCellCount(20)));
+        assert_eq!(
+            fold(&["###.", "...#", "..##"], 3),
+            Err(CubeError::NotConnected)
+        );
+    }
+}
@@ -1,5 +1,13 @@
+mod cube;
 mod parser;
 mod part1;
 mod part2;
+mod trace;
+mod validation;
 
-pub use parser::Problem;
+pub use cube::{CubeError, CubeFace, CubeNet};
+pub use parser::{Direction, Instructions, ParsedOutput, Points, Problem};
+pub use part1::{get_new_pos, get_path, WrapFunction, WrapPointState};
+pub use part2::stitch_cube;
+pub use trace::{render_cube_faces, render_path};
+pub use validation::{StitchReport, StitchViolation};
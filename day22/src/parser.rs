@@ -1,3 +1,4 @@
+use crate::part1::WrapPointState;
 use utils::{
     nom::{
         branch::alt,
@@ -26,7 +27,7 @@ impl ParsedOutput {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub enum Direction {
     North,
     South,
@@ -39,6 +40,14 @@ pub enum Direction {
 pub struct Problem {
     pub position: (usize, usize),
     pub direction: Direction,
+    // Prints a report on the cube's stitches before walking part 2
+    pub check_stitches: bool,
+    // Every cell the walker went through and the way it was facing, in order
+    pub path: Vec<WrapPointState>,
+    // Prints the map with the path drawn over it once the walk is done
+    pub print_path: bool,
+    // Prints the faces of the cube with the path once part 2 is done
+    pub print_cube: bool,
 }
 
 impl Direction {
@@ -50,6 +59,14 @@ impl Direction {
             Direction::West => 2,
         }
     }
+    pub fn get_arrow(&self) -> char {
+        match self {
+            Direction::North => '^',
+            Direction::South => 'v',
+            Direction::East => '>',
+            Direction::West => '<',
+        }
+    }
     pub fn opposite(&self) -> Direction {
         match self {
             Direction::North => Direction::South,
@@ -136,7 +153,10 @@ fn parse_instructions(input: &str) -> IResult<&str, Vec<Instructions>> {
     )))(input)
 }
 
-fn parse_line(input: &str) -> IResult<&str, (Option<Vec<Instructions>>, Option<Vec<Points>>)> {
+// Either the instructions or a row of the map
+type ParsedLine = (Option<Vec<Instructions>>, Option<Vec<Points>>);
+
+fn parse_line(input: &str) -> IResult<&str, ParsedLine> {
     let (input, instructions) = opt(parse_instructions)(input)?;
     if let Some(instructions) = instructions {
         return Ok((input, (Some(instructions), None)));
@@ -1,47 +1,30 @@
-use utils::{clear_screen, problem::SolvePart1, wait_for_input};
+use std::fmt;
+use utils::problem::SolvePart1;
 
 use crate::parser::{Direction, Instructions, ParsedOutput, Points, Problem};
+use crate::trace::render_path;
 
 pub type WrapFunction = Box<dyn Fn((usize, usize), &ParsedOutput, Direction) -> WrapPointState>;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WrapPointState(pub (usize, usize), pub Direction);
 
-#[allow(dead_code)]
-pub fn print_map(map: &[Vec<Points>], problem: &Problem, string: String) {
-    clear_screen();
-    println!("Position: {:?}", problem.position);
-    for (y, row) in map.iter().enumerate() {
-        for (x, point) in row.iter().enumerate() {
-            match point {
-                Points::Empty => {
-                    if problem.position.0 == x && problem.position.1 == y {
-                        match problem.direction {
-                            Direction::North => print!("^"),
-                            Direction::South => print!("v"),
-                            Direction::East => print!(">"),
-                            Direction::West => print!("<"),
-                        }
-                    } else {
-                        print!(".")
-                    }
-                }
-                Points::Wall => print!("#"),
-                Points::OutOfBounds => print!(" "),
-            }
-        }
-        println!();
+// (12, 6) facing East
+impl fmt::Display for WrapPointState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} facing {:?}", self.0, self.1)
     }
-    println!("{}", string);
-
-    wait_for_input();
 }
 
 impl Problem {
-    pub fn solve(&mut self, parsed_input: ParsedOutput, get_warped_point: &WrapFunction) -> String {
-        parsed_input.instructions.iter().for_each(|instruction| {
-            // print_map(&parsed_input.map, self, format!("{:?}", instruction));
+    pub fn solve(
+        &mut self,
+        parsed_input: &ParsedOutput,
+        get_warped_point: &WrapFunction,
+    ) -> String {
+        self.path = vec![WrapPointState(self.position, self.direction)];
 
+        parsed_input.instructions.iter().for_each(|instruction| {
             match instruction {
                 Instructions::Left => {
                     self.direction = match self.direction {
@@ -60,13 +43,23 @@ impl Problem {
                     }
                 }
                 Instructions::Move(value) => {
-                    let new_data = get_new_pos(self, &parsed_input, *value, get_warped_point);
-                    self.position = new_data.0;
-                    self.direction = new_data.1;
+                    let path = get_path(self, parsed_input, *value, get_warped_point);
+                    if let Some(new_data) = path.last() {
+                        self.position = new_data.0;
+                        self.direction = new_data.1;
+                    }
+                    self.path.extend(path);
+                    return;
                 }
             };
+            self.path
+                .push(WrapPointState(self.position, self.direction));
         });
 
+        if self.print_path {
+            println!("{}", render_path(parsed_input, &self.path));
+        }
+
         format!(
             "{}",
             1000 * (self.position.1) + 4 * (self.position.0) + self.direction.get_direction_value()
@@ -78,7 +71,7 @@ impl SolvePart1 for Problem {
     type ParsedType = ParsedOutput;
 
     fn solve_part_one(&mut self, parsed_input: ParsedOutput) -> String {
-        self.solve(parsed_input, &(Box::new(get_warped_point) as WrapFunction))
+        self.solve(&parsed_input, &(Box::new(get_warped_point) as WrapFunction))
     }
 }
 
@@ -88,6 +81,20 @@ pub fn get_new_pos(
     move_distance: u32,
     get_warped_point: &WrapFunction,
 ) -> WrapPointState {
+    get_path(problem, parsed_input, move_distance, get_warped_point)
+        .last()
+        .copied()
+        .unwrap_or(WrapPointState(problem.position, problem.direction))
+}
+
+// Every cell the move goes through, in order, until it's done or hits a wall
+pub fn get_path(
+    problem: &Problem,
+    parsed_input: &ParsedOutput,
+    move_distance: u32,
+    get_warped_point: &WrapFunction,
+) -> Vec<WrapPointState> {
+    let mut path = vec![];
     let mut return_pos = problem.position;
     let mut direction = problem.direction;
     for _ in 0..move_distance {
@@ -117,9 +124,10 @@ pub fn get_new_pos(
                 return_pos = new_pos;
             }
         }
+        path.push(WrapPointState(return_pos, direction));
     }
 
-    WrapPointState(return_pos, direction)
+    path
 }
 
 fn get_warped_point(
@@ -0,0 +1,141 @@
+use crate::cube::{CubeFace, CubeNet};
+use crate::parser::{Direction, ParsedOutput, Points};
+use crate::part1::WrapPointState;
+use std::collections::HashMap;
+
+// Last way the walker faced on every cell it went through
+fn get_arrows(path: &[WrapPointState]) -> HashMap<(usize, usize), char> {
+    path.iter()
+        .map(|WrapPointState(position, direction)| (*position, direction.get_arrow()))
+        .collect()
+}
+
+fn get_cell(parsed_input: &ParsedOutput, position: (usize, usize)) -> char {
+    match parsed_input.get_point(position) {
+        Points::Empty => '.',
+        Points::Wall => '#',
+        Points::OutOfBounds => ' ',
+    }
+}
+
+// The map with the path drawn over it, the same way as the puzzle's example
+pub fn render_path(parsed_input: &ParsedOutput, path: &[WrapPointState]) -> String {
+    let arrows = get_arrows(path);
+
+    parsed_input
+        .map
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(y, row)| {
+            (1..row.len())
+                .map(|x| {
+                    arrows
+                        .get(&(x, y))
+                        .copied()
+                        .unwrap_or_else(|| get_cell(parsed_input, (x, y)))
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Every face of the cube on its own, named after the side of the cube it ends up on, with the
+// faces it's next to. The border shows where the walker crossed over to another face, with the
+// way it was going when it left or came in.
+pub fn render_cube_faces(
+    cube: &CubeNet,
+    parsed_input: &ParsedOutput,
+    path: &[WrapPointState],
+) -> String {
+    let arrows = get_arrows(path);
+    let size = cube.face_size;
+
+    // Face tile, side and offset along the side, always from the top or from the left
+    let mut crossings: HashMap<((usize, usize), Direction, usize), char> = HashMap::new();
+    let mut mark = |face: &CubeFace, side: Direction, (x, y): (usize, usize), arrow: char| {
+        let (x, y) = (x - 1 - face.tile.0 * size, y - 1 - face.tile.1 * size);
+        let offset = match side {
+            Direction::North | Direction::South => x,
+            Direction::East | Direction::West => y,
+        };
+        crossings.insert((face.tile, side, offset), arrow);
+    };
+
+    for step in path.windows(2) {
+        let (WrapPointState(from, leaving), WrapPointState(to, entering)) = (step[0], step[1]);
+        let (Some(from_face), Some(to_face)) = (cube.get_face_at(from), cube.get_face_at(to))
+        else {
+            continue;
+        };
+        if from_face.tile == to_face.tile {
+            continue;
+        }
+        mark(from_face, leaving, from, leaving.get_arrow());
+        mark(to_face, entering.opposite(), to, entering.get_arrow());
+    }
+
+    let get_border = |face: &CubeFace, side: Direction, offset: usize, default: char| {
+        crossings
+            .get(&(face.tile, side, offset))
+            .copied()
+            .unwrap_or(default)
+    };
+
+    let mut panels = vec![];
+    for face in cube.faces.iter() {
+        let neighbours = [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ]
+        .map(|direction| {
+            format!(
+                "{:?} {}",
+                direction,
+                cube.get_neighbour(face, direction).0.get_label()
+            )
+        });
+        let mut lines = vec![format!(
+            "{} (tile {:?}): {}",
+            face.get_label(),
+            face.tile,
+            neighbours.join(", ")
+        )];
+
+        let border = |side: Direction| {
+            let edge: String = (0..size)
+                .map(|offset| get_border(face, side, offset, '-'))
+                .collect();
+            format!("+{}+", edge)
+        };
+
+        lines.push(border(Direction::North));
+        for y in 0..size {
+            let cells: String = (0..size)
+                .map(|x| {
+                    let position = (1 + face.tile.0 * size + x, 1 + face.tile.1 * size + y);
+                    arrows
+                        .get(&position)
+                        .copied()
+                        .unwrap_or_else(|| get_cell(parsed_input, position))
+                })
+                .collect();
+            lines.push(format!(
+                "{}{}{}",
+                get_border(face, Direction::West, y, '|'),
+                cells,
+                get_border(face, Direction::East, y, '|')
+            ));
+        }
+        lines.push(border(Direction::South));
+
+        panels.push(lines.join("\n"));
+    }
+
+    panels.join("\n\n")
+}
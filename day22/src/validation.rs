@@ -0,0 +1,376 @@
+use crate::parser::{Direction, ParsedOutput, Points};
+use crate::part1::WrapPointState;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+// A side of a face: the face's tile and the direction the side is in
+type FaceEdge = ((usize, usize), Direction);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StitchViolation {
+    // Walking off the map here has nowhere to go
+    Missing(WrapPointState),
+    // Stitched, but walking this way doesn't leave the map
+    Unexpected(WrapPointState),
+    // Lands outside the map
+    OffMap {
+        from: WrapPointState,
+        to: WrapPointState,
+    },
+    // Two ways of walking off the map land on the same cell facing the same way
+    SharedTarget {
+        first: WrapPointState,
+        second: WrapPointState,
+        to: WrapPointState,
+    },
+    // Turning around after crossing doesn't cross back to where the walker came from, with
+    // where it ends up instead
+    NotReversible {
+        from: WrapPointState,
+        to: WrapPointState,
+        back: Option<WrapPointState>,
+    },
+    // The cells along one side of a face are spread over several sides
+    SplitEdge {
+        edge: FaceEdge,
+        targets: Vec<FaceEdge>,
+    },
+    // Several sides are stitched onto the same side
+    SharedEdge {
+        edges: Vec<FaceEdge>,
+        target: FaceEdge,
+    },
+}
+
+fn fmt_edge((tile, side): &FaceEdge) -> String {
+    format!("the {:?} side of the face at tile {:?}", side, tile)
+}
+
+impl fmt::Display for StitchViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StitchViolation::Missing(state) => {
+                write!(f, "Walking off the map from {} isn't stitched", state)
+            }
+            StitchViolation::Unexpected(state) => write!(
+                f,
+                "{} is stitched but walking that way stays on the map",
+                state
+            ),
+            StitchViolation::OffMap { from, to } => {
+                write!(f, "{} lands on {}, outside the map", from, to)
+            }
+            StitchViolation::SharedTarget { first, second, to } => {
+                write!(f, "{} and {} both land on {}", first, second, to)
+            }
+            StitchViolation::NotReversible { from, to, back } => {
+                write!(f, "{} lands on {} but turning around ", from, to)?;
+                match back {
+                    Some(back) => write!(f, "lands on {}", back),
+                    None => write!(f, "isn't stitched"),
+                }
+            }
+            StitchViolation::SplitEdge { edge, targets } => write!(
+                f,
+                "Cells on {} land on {}",
+                fmt_edge(edge),
+                targets
+                    .iter()
+                    .map(fmt_edge)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            StitchViolation::SharedEdge { edges, target } => write!(
+                f,
+                "Cells on {} all land on {}",
+                edges
+                    .iter()
+                    .map(fmt_edge)
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                fmt_edge(target)
+            ),
+        }
+    }
+}
+
+// What checking the stitches found, a valid stitching has no violations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StitchReport {
+    pub stitches: usize,
+    pub edges: usize,
+    pub violations: Vec<StitchViolation>,
+}
+
+impl StitchReport {
+    // Every way of walking off the map has to be stitched, and nothing else. Stitches have to
+    // work both ways, walking across and turning around leads back to the start facing the
+    // opposite way, and whole sides of faces have to be stitched to whole sides.
+    pub fn new(
+        parsed_input: &ParsedOutput,
+        stitches: &HashMap<WrapPointState, WrapPointState>,
+        face_size: usize,
+    ) -> StitchReport {
+        let mut violations = vec![];
+        let is_on_map =
+            |point: (usize, usize)| !matches!(parsed_input.get_point(point), Points::OutOfBounds);
+
+        // Sorted so the report comes out the same every time
+        let mut expected: BTreeSet<WrapPointState> = BTreeSet::new();
+        for (y, row) in parsed_input.map.iter().enumerate() {
+            for x in 0..row.len() {
+                if !is_on_map((x, y)) {
+                    continue;
+                }
+                for direction in [
+                    Direction::North,
+                    Direction::East,
+                    Direction::South,
+                    Direction::West,
+                ] {
+                    if !is_on_map(direction.get_new_point((x, y), 1)) {
+                        expected.insert(WrapPointState((x, y), direction));
+                    }
+                }
+            }
+        }
+
+        let sorted: BTreeMap<WrapPointState, WrapPointState> =
+            stitches.iter().map(|(from, to)| (*from, *to)).collect();
+
+        violations.extend(
+            expected
+                .iter()
+                .filter(|state| !sorted.contains_key(state))
+                .map(|state| StitchViolation::Missing(*state)),
+        );
+        violations.extend(
+            sorted
+                .keys()
+                .filter(|state| !expected.contains(state))
+                .map(|state| StitchViolation::Unexpected(*state)),
+        );
+
+        let mut sources: BTreeMap<WrapPointState, WrapPointState> = BTreeMap::new();
+        let mut edge_targets: BTreeMap<FaceEdge, BTreeSet<FaceEdge>> = BTreeMap::new();
+        let get_edge = |WrapPointState((x, y), side): WrapPointState| {
+            (((x - 1) / face_size, (y - 1) / face_size), side)
+        };
+
+        for (from, to) in sorted.iter() {
+            if !is_on_map(to.0) {
+                violations.push(StitchViolation::OffMap {
+                    from: *from,
+                    to: *to,
+                });
+                continue;
+            }
+
+            if let Some(first) = sources.insert(*to, *from) {
+                violations.push(StitchViolation::SharedTarget {
+                    first,
+                    second: *from,
+                    to: *to,
+                });
+            }
+
+            let turned_around = WrapPointState(to.0, to.1.opposite());
+            let back = sorted.get(&turned_around).copied();
+            if back != Some(WrapPointState(from.0, from.1.opposite())) {
+                violations.push(StitchViolation::NotReversible {
+                    from: *from,
+                    to: *to,
+                    back,
+                });
+            }
+
+            if expected.contains(from) {
+                edge_targets
+                    .entry(get_edge(*from))
+                    .or_default()
+                    .insert(get_edge(turned_around));
+            }
+        }
+
+        let mut edge_sources: BTreeMap<FaceEdge, Vec<FaceEdge>> = BTreeMap::new();
+        for (edge, targets) in edge_targets.iter() {
+            if targets.len() > 1 {
+                violations.push(StitchViolation::SplitEdge {
+                    edge: *edge,
+                    targets: targets.iter().copied().collect(),
+                });
+            }
+            for target in targets {
+                edge_sources.entry(*target).or_default().push(*edge);
+            }
+        }
+        violations.extend(
+            edge_sources
+                .into_iter()
+                .filter(|(_, edges)| edges.len() > 1)
+                .map(|(target, edges)| StitchViolation::SharedEdge { edges, target }),
+        );
+
+        StitchReport {
+            stitches: stitches.len(),
+            edges: edge_targets.len(),
+            violations,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for StitchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Checked {} stitches over {} face edges: ",
+            self.stitches, self.edges
+        )?;
+        if self.is_valid() {
+            return write!(f, "every stitch is consistent");
+        }
+
+        write!(f, "{} problems", self.violations.len())?;
+        for violation in self.violations.iter() {
+            write!(f, "\n  {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::CubeNet;
+    use crate::parser::Problem;
+    use utils::parser::ParseInput;
+
+    fn state(position: (usize, usize), direction: Direction) -> WrapPointState {
+        WrapPointState(position, direction)
+    }
+
+    // The puzzle's example folded into a cube, stitched the right way
+    fn get_sample() -> (ParsedOutput, HashMap<WrapPointState, WrapPointState>) {
+        let parsed_input =
+            Problem::default().parse(include_str!("../input/sample-input.txt").to_string(), true);
+        let stitches = CubeNet::fold(&parsed_input).unwrap().get_stitches();
+        (parsed_input, stitches)
+    }
+
+    #[test]
+    fn sample_stitches_are_valid() {
+        let (parsed_input, stitches) = get_sample();
+        let report = StitchReport::new(&parsed_input, &stitches, 4);
+
+        assert!(report.is_valid());
+        assert_eq!(
+            report.to_string(),
+            "Checked 56 stitches over 14 face edges: every stitch is consistent"
+        );
+    }
+
+    // Walking north off (1, 5) lands on (12, 1) facing south, pointing it at where walking east
+    // off (16, 12) lands instead breaks both stitches and the edges they are on
+    #[test]
+    fn stitch_pointed_at_another_target() {
+        let (parsed_input, mut stitches) = get_sample();
+        let target = stitches[&state((16, 12), Direction::East)];
+        assert_eq!(target, state((12, 1), Direction::West));
+        stitches.insert(state((1, 5), Direction::North), target);
+
+        let report = StitchReport::new(&parsed_input, &stitches, 4);
+        assert_eq!(
+            report.violations,
+            vec![
+                StitchViolation::NotReversible {
+                    from: state((1, 5), Direction::North),
+                    to: state((12, 1), Direction::West),
+                    back: Some(state((16, 12), Direction::West)),
+                },
+                StitchViolation::NotReversible {
+                    from: state((12, 1), Direction::North),
+                    to: state((1, 5), Direction::South),
+                    back: Some(state((12, 1), Direction::West)),
+                },
+                StitchViolation::SharedTarget {
+                    first: state((1, 5), Direction::North),
+                    second: state((16, 12), Direction::East),
+                    to: state((12, 1), Direction::West),
+                },
+                StitchViolation::SplitEdge {
+                    edge: ((0, 1), Direction::North),
+                    targets: vec![((2, 0), Direction::North), ((2, 0), Direction::East)],
+                },
+                StitchViolation::SharedEdge {
+                    edges: vec![((0, 1), Direction::North), ((3, 2), Direction::East)],
+                    target: ((2, 0), Direction::East),
+                },
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "Checked 56 stitches over 14 face edges: 5 problems
+  (1, 5) facing North lands on (12, 1) facing West but turning around lands on (16, 12) facing West
+  (12, 1) facing North lands on (1, 5) facing South but turning around lands on (12, 1) facing West
+  (1, 5) facing North and (16, 12) facing East both land on (12, 1) facing West
+  Cells on the North side of the face at tile (0, 1) land on the North side of the face at tile (2, 0), the East side of the face at tile (2, 0)
+  Cells on the North side of the face at tile (0, 1), the East side of the face at tile (3, 2) all land on the East side of the face at tile (2, 0)"
+        );
+    }
+
+    #[test]
+    fn missing_unexpected_and_off_map_stitches() {
+        let (parsed_input, mut stitches) = get_sample();
+        stitches.remove(&state((1, 5), Direction::North));
+        stitches.insert(
+            state((9, 2), Direction::South),
+            state((9, 3), Direction::South),
+        );
+        stitches.insert(
+            state((16, 12), Direction::East),
+            state((20, 20), Direction::West),
+        );
+
+        let report = StitchReport::new(&parsed_input, &stitches, 4);
+        assert_eq!(
+            report.violations,
+            vec![
+                StitchViolation::Missing(state((1, 5), Direction::North)),
+                StitchViolation::Unexpected(state((9, 2), Direction::South)),
+                StitchViolation::NotReversible {
+                    from: state((9, 2), Direction::South),
+                    to: state((9, 3), Direction::South),
+                    back: None,
+                },
+                StitchViolation::NotReversible {
+                    from: state((12, 1), Direction::North),
+                    to: state((1, 5), Direction::South),
+                    back: None,
+                },
+                StitchViolation::NotReversible {
+                    from: state((12, 1), Direction::East),
+                    to: state((16, 12), Direction::West),
+                    back: Some(state((20, 20), Direction::West)),
+                },
+                StitchViolation::OffMap {
+                    from: state((16, 12), Direction::East),
+                    to: state((20, 20), Direction::West),
+                },
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "Checked 56 stitches over 14 face edges: 6 problems
+  Walking off the map from (1, 5) facing North isn't stitched
+  (9, 2) facing South is stitched but walking that way stays on the map
+  (9, 2) facing South lands on (9, 3) facing South but turning around isn't stitched
+  (12, 1) facing North lands on (1, 5) facing South but turning around isn't stitched
+  (12, 1) facing East lands on (16, 12) facing West but turning around lands on (20, 20) facing West
+  (16, 12) facing East lands on (20, 20) facing West, outside the map"
+        );
+    }
+}